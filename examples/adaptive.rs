@@ -0,0 +1,49 @@
+use ray_tracing_in_one_week_rust::adaptive::AdaptiveSampling;
+use ray_tracing_in_one_week_rust::camera::Camera;
+use ray_tracing_in_one_week_rust::environment::gradient_sky::GradientSky;
+use ray_tracing_in_one_week_rust::render::{render_with_sample_counts, RenderSettings};
+use ray_tracing_in_one_week_rust::scenes::three_spheres;
+use ray_tracing_in_one_week_rust::vector3::{Point3, Vector3};
+use std::env;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+
+fn main() {
+    let out_dir = PathBuf::from(env::args().nth(1).unwrap_or_else(|| ".".to_string()));
+
+    // Image
+    let aspect_ratio = 3.0 / 2.0;
+    let image_width = 600usize;
+    let mut settings =
+        RenderSettings::new(image_width, (image_width as f64 / aspect_ratio) as usize);
+    settings.adaptive = Some(AdaptiveSampling::default());
+
+    // World
+    let world = three_spheres(0).world;
+    let sky = GradientSky::default();
+
+    // Camera
+    let camera = Camera::new(
+        Point3::new(13.0, 2.0, 3.0),
+        Point3::zero(),
+        Vector3::new_y(1.0),
+        30.0,
+        aspect_ratio,
+        0.0,
+        10.0,
+        0.0,
+        1.0,
+    );
+
+    // Render
+    let (image, sample_counts) = render_with_sample_counts(&world, &camera, &sky, &settings);
+    eprintln!("mean samples per pixel: {:.1}", sample_counts.mean());
+
+    for (name, image) in [("adaptive", &image), ("samples", &sample_counts.heatmap())] {
+        let mut out = BufWriter::new(File::create(out_dir.join(format!("{}.ppm", name))).unwrap());
+        image.write_ppm(&mut out, &settings.tone_mapping).unwrap();
+    }
+
+    eprintln!("\nDone");
+}
@@ -0,0 +1,56 @@
+use ray_tracing_in_one_week_rust::camera::Camera;
+use ray_tracing_in_one_week_rust::environment::gradient_sky::GradientSky;
+use ray_tracing_in_one_week_rust::progressive::{render_with_budget, RenderBudget};
+use ray_tracing_in_one_week_rust::render::RenderSettings;
+use ray_tracing_in_one_week_rust::scenes::three_spheres;
+use ray_tracing_in_one_week_rust::vector3::{Point3, Vector3};
+use std::env;
+use std::io::{stdout, BufWriter};
+use std::time::Duration;
+
+fn main() {
+    // Seconds to render for, and the mean relative error to stop at
+    let budget = RenderBudget {
+        time_limit: Some(Duration::from_secs_f64(
+            env::args().nth(1).map_or(30.0, |s| s.parse().unwrap()),
+        )),
+        target_error: Some(env::args().nth(2).map_or(0.01, |s| s.parse().unwrap())),
+        ..RenderBudget::default()
+    };
+
+    // Image
+    let aspect_ratio = 3.0 / 2.0;
+    let image_width = 600usize;
+    let mut settings =
+        RenderSettings::new(image_width, (image_width as f64 / aspect_ratio) as usize);
+    settings.samples_per_pixel = 4;
+
+    // World
+    let world = three_spheres(0).world;
+    let sky = GradientSky::default();
+
+    // Camera
+    let camera = Camera::new(
+        Point3::new(13.0, 2.0, 3.0),
+        Point3::zero(),
+        Vector3::new_y(1.0),
+        30.0,
+        aspect_ratio,
+        0.1,
+        10.0,
+        0.0,
+        1.0,
+    );
+
+    // Render
+    let (image, report) = render_with_budget(&world, &camera, &sky, &settings, &budget);
+    eprintln!(
+        "{} samples per pixel, {:.2}% mean relative error, {:.1}s",
+        report.samples_per_pixel,
+        report.mean_relative_error * 100.0,
+        report.elapsed.as_secs_f64()
+    );
+
+    let mut out = BufWriter::new(stdout());
+    image.write_ppm(&mut out, &settings.tone_mapping).unwrap();
+}
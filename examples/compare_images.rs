@@ -0,0 +1,82 @@
+//! Compares a test image with a reference:
+//!
+//!     cargo run --release --example compare_images -- reference.png test.ppm [diff.png]
+//!
+//! PPM and PNG files hold display values. PFM and HDR files hold linear radiance and are tone
+//! mapped the way the renderer writes 8-bit files, so that every metric sees display values. The
+//! difference image shows the per-pixel FLIP error in false color.
+
+use ray_tracing_in_one_week_rust::image::Image;
+use ray_tracing_in_one_week_rust::image_metrics::{compare, flip_image};
+use ray_tracing_in_one_week_rust::tone_mapping::{ToneMapOperator, ToneMapping, TransferFunction};
+use std::env;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter};
+use std::path::Path;
+use std::process;
+
+fn open(path: &str) -> io::Result<Image> {
+    let mut input = BufReader::new(File::open(path)?);
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase());
+
+    let tone_mapping = ToneMapping::default();
+    let tone_map = |image: Image| {
+        let pixels = image.pixels().iter().map(|c| tone_mapping.map(c)).collect();
+        Image::from_pixels(image.width(), image.height(), pixels)
+    };
+    match extension.as_deref() {
+        Some("ppm") => Image::read_ppm(&mut input),
+        Some("png") => Image::read_png(&mut input),
+        Some("pfm") => Image::read_pfm(&mut input).map(tone_map),
+        Some("hdr") => Image::read_hdr(&mut input).map(tone_map),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "expected a .ppm, .png, .pfm or .hdr file",
+        )),
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.len() < 2 || args.len() > 3 {
+        eprintln!("usage: compare_images REFERENCE TEST [DIFF.png]");
+        process::exit(2);
+    }
+
+    let images = [&args[0], &args[1]].map(|path| {
+        open(path).unwrap_or_else(|e| {
+            eprintln!("{}: {}", path, e);
+            process::exit(1);
+        })
+    });
+    let [reference, test] = images;
+    if (reference.width(), reference.height()) != (test.width(), test.height()) {
+        eprintln!(
+            "the images differ in size: {}x{} and {}x{}",
+            reference.width(),
+            reference.height(),
+            test.width(),
+            test.height()
+        );
+        process::exit(1);
+    }
+
+    let comparison = compare(&reference, &test);
+    println!("size        {}x{}", reference.width(), reference.height());
+    println!("mean error  {:.6}", comparison.mean_absolute_error);
+    println!("rmse        {:.6}", comparison.rmse);
+    println!("psnr        {:.2} dB", comparison.psnr);
+    println!("ssim        {:.4}", comparison.ssim);
+    println!("flip        {:.4}", comparison.flip);
+
+    if let Some(path) = args.get(2) {
+        let tone_mapping = ToneMapping::new(ToneMapOperator::Clamp, TransferFunction::Linear);
+        let mut out = BufWriter::new(File::create(path).unwrap());
+        flip_image(&reference, &test)
+            .write_png(&mut out, &tone_mapping)
+            .unwrap();
+    }
+}
@@ -0,0 +1,48 @@
+use ray_tracing_in_one_week_rust::camera::Camera;
+use ray_tracing_in_one_week_rust::environment::gradient_sky::GradientSky;
+use ray_tracing_in_one_week_rust::path_inspector::{inspect_pixel, write_obj};
+use ray_tracing_in_one_week_rust::render::RenderSettings;
+use ray_tracing_in_one_week_rust::scenes::three_spheres;
+use ray_tracing_in_one_week_rust::vector3::{Point3, Vector3};
+use std::env;
+use std::fs::File;
+use std::io::BufWriter;
+
+fn main() {
+    // The pixel to look at, as column and row from the top
+    let x = env::args().nth(1).map_or(300, |s| s.parse().unwrap());
+    let y = env::args().nth(2).map_or(200, |s| s.parse().unwrap());
+
+    // Image
+    let aspect_ratio = 3.0 / 2.0;
+    let image_width = 600usize;
+    let mut settings =
+        RenderSettings::new(image_width, (image_width as f64 / aspect_ratio) as usize);
+    settings.samples_per_pixel = 8;
+
+    // World
+    let world = three_spheres(0).world;
+    let sky = GradientSky::default();
+
+    // Camera
+    let camera = Camera::new(
+        Point3::new(13.0, 2.0, 3.0),
+        Point3::zero(),
+        Vector3::new_y(1.0),
+        30.0,
+        aspect_ratio,
+        0.1,
+        10.0,
+        0.0,
+        1.0,
+    );
+
+    // Trace
+    let paths = inspect_pixel(&world, &camera, &sky, &settings, (x, y));
+    for path in &paths {
+        println!("{}", path);
+    }
+
+    let mut out = BufWriter::new(File::create("paths.obj").unwrap());
+    write_obj(&mut out, &paths, 20.0).unwrap();
+}
@@ -0,0 +1,73 @@
+use itertools::Itertools;
+use rand::{thread_rng, Rng};
+use ray_tracing_in_one_week_rust::camera::Camera;
+use ray_tracing_in_one_week_rust::degrees_to_radians;
+use ray_tracing_in_one_week_rust::environment::physical_sky::PhysicalSky;
+use ray_tracing_in_one_week_rust::render::ray_color;
+use ray_tracing_in_one_week_rust::scenes::three_spheres;
+use ray_tracing_in_one_week_rust::vector3::{Color, Point3, Vector3};
+use rayon::prelude::*;
+
+fn main() {
+    // Image
+    let aspect_ratio = 3.0 / 2.0;
+    let image_width = 600usize;
+    let image_height = (image_width as f64 / aspect_ratio) as usize;
+    let samples_per_pixel = 100;
+    let max_depth = 50;
+
+    // World
+    let world = three_spheres(0).world;
+    let sky = PhysicalSky::new(degrees_to_radians(15.0), degrees_to_radians(30.0), 3.0);
+
+    // Camera
+    let camera = Camera::new(
+        Point3::new(13.0, 2.0, 3.0),
+        Point3::zero(),
+        Vector3::new_y(1.0),
+        30.0,
+        aspect_ratio,
+        0.1,
+        10.0,
+        0.0,
+        1.0,
+    );
+
+    // Render
+
+    println!("P3");
+    println!("{} {}", image_width, image_height);
+    println!("255");
+
+    let pixels: Vec<Vec<Color>> = (0..image_height)
+        .rev()
+        .collect_vec()
+        .into_par_iter()
+        .map(|j| {
+            let mut rng = thread_rng();
+            (0..image_width)
+                .map(|i| {
+                    let pixel_color: Vector3 = (0..samples_per_pixel)
+                        .map(|_| {
+                            let u = (i as f64 + rng.gen::<f64>()) / (image_width - 1) as f64;
+                            let v = (j as f64 + rng.gen::<f64>()) / (image_height - 1) as f64;
+                            let r = camera.ray(&mut rng, u, v);
+
+                            Vector3::from(ray_color(&mut rng, &r, &world, &sky, max_depth))
+                                / samples_per_pixel as f64
+                        })
+                        .sum();
+                    Color::from(pixel_color.sqrt())
+                })
+                .collect()
+        })
+        .collect();
+
+    for row in pixels {
+        for pixel_color in row {
+            println!("{}", pixel_color);
+        }
+    }
+
+    eprintln!("\nDone");
+}
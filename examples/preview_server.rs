@@ -0,0 +1,82 @@
+use ray_tracing_in_one_week_rust::camera::Camera;
+use ray_tracing_in_one_week_rust::environment::gradient_sky::GradientSky;
+use ray_tracing_in_one_week_rust::preview_server::{PreviewServer, PreviewStatus};
+use ray_tracing_in_one_week_rust::progressive::ProgressiveRender;
+use ray_tracing_in_one_week_rust::render::RenderSettings;
+use ray_tracing_in_one_week_rust::scenes::three_spheres;
+use ray_tracing_in_one_week_rust::scheduler::CancellationToken;
+use ray_tracing_in_one_week_rust::telemetry;
+use ray_tracing_in_one_week_rust::vector3::{Point3, Vector3};
+use std::env;
+use std::time::{Duration, Instant};
+
+fn main() {
+    let port = env::args().nth(1).map_or(8000, |s| s.parse().unwrap());
+    let passes = env::args().nth(2).map_or(64, |s| s.parse().unwrap());
+
+    // Image
+    let aspect_ratio = 3.0 / 2.0;
+    let image_width = 600usize;
+    let mut settings =
+        RenderSettings::new(image_width, (image_width as f64 / aspect_ratio) as usize);
+    settings.samples_per_pixel = 4;
+    let pixels = settings.image_width * settings.image_height;
+
+    // World
+    let world = three_spheres(0).world;
+    let sky = GradientSky::default();
+
+    // Camera
+    let camera = Camera::new(
+        Point3::new(13.0, 2.0, 3.0),
+        Point3::zero(),
+        Vector3::new_y(1.0),
+        30.0,
+        aspect_ratio,
+        0.1,
+        10.0,
+        0.0,
+        1.0,
+    );
+
+    // Render
+    let server = PreviewServer::start(port).unwrap();
+    eprintln!("watch at http://{}/", server.address());
+
+    telemetry::take();
+    let started = Instant::now();
+    let status = |passes_done: f64| {
+        let elapsed = started.elapsed().as_secs_f64();
+        // The counters only count when the telemetry feature is on
+        let rays_per_second =
+            cfg!(feature = "telemetry").then(|| telemetry::totals().rays() as f64 / elapsed);
+        PreviewStatus {
+            passes: passes_done as usize,
+            samples_per_pixel: passes_done as usize * settings.samples_per_pixel,
+            eta: (passes_done > 0.0).then(|| {
+                Duration::from_secs_f64(elapsed * (passes as f64 - passes_done) / passes_done)
+            }),
+            samples_per_second: passes_done * (settings.samples_per_pixel * pixels) as f64
+                / elapsed,
+            rays_per_second,
+        }
+    };
+
+    let mut render = ProgressiveRender::new(&settings);
+    while render.passes() < passes {
+        let passes_done = render.passes() as f64;
+        render.render_pass_with_progress(
+            &world,
+            &camera,
+            &sky,
+            &|progress| server.update_status(status(passes_done + progress.fraction())),
+            &CancellationToken::new(),
+        );
+
+        server.update_image(&render.image(), &settings.tone_mapping);
+        server.update_status(status(render.passes() as f64));
+    }
+
+    eprintln!("Done, press enter to stop serving");
+    std::io::stdin().read_line(&mut String::new()).unwrap();
+}
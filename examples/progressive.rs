@@ -0,0 +1,66 @@
+use ray_tracing_in_one_week_rust::camera::Camera;
+use ray_tracing_in_one_week_rust::environment::gradient_sky::GradientSky;
+use ray_tracing_in_one_week_rust::progressive::ProgressiveRender;
+use ray_tracing_in_one_week_rust::render::RenderSettings;
+use ray_tracing_in_one_week_rust::scenes::three_spheres;
+use ray_tracing_in_one_week_rust::vector3::{Point3, Vector3};
+use std::env;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter};
+use std::path::PathBuf;
+
+fn main() {
+    let out_dir = PathBuf::from(env::args().nth(1).unwrap_or_else(|| ".".to_string()));
+    let passes = env::args().nth(2).map_or(8, |s| s.parse().unwrap());
+    let checkpoint_path = out_dir.join("progressive.checkpoint");
+
+    // Image
+    let aspect_ratio = 3.0 / 2.0;
+    let image_width = 600usize;
+    let mut settings =
+        RenderSettings::new(image_width, (image_width as f64 / aspect_ratio) as usize);
+    settings.samples_per_pixel = 8;
+
+    // World
+    let world = three_spheres(0).world;
+    let sky = GradientSky::default();
+
+    // Camera
+    let camera = Camera::new(
+        Point3::new(13.0, 2.0, 3.0),
+        Point3::zero(),
+        Vector3::new_y(1.0),
+        30.0,
+        aspect_ratio,
+        0.1,
+        10.0,
+        0.0,
+        1.0,
+    );
+
+    // Render, picking up where the last run stopped
+    let mut render = match File::open(&checkpoint_path) {
+        Ok(file) => ProgressiveRender::resume(&settings, &mut BufReader::new(file)).unwrap(),
+        Err(_) => ProgressiveRender::new(&settings),
+    };
+
+    while render.passes() < passes {
+        render.render_pass(&world, &camera, &sky);
+        eprintln!("{} samples per pixel", render.samples_per_pixel());
+
+        let mut out = BufWriter::new(File::create(out_dir.join("progressive.ppm")).unwrap());
+        render
+            .image()
+            .write_ppm(&mut out, &settings.tone_mapping)
+            .unwrap();
+
+        // Written aside and renamed, so dying mid-write keeps the previous checkpoint
+        let partial_path = checkpoint_path.with_extension("partial");
+        let mut out = BufWriter::new(File::create(&partial_path).unwrap());
+        render.write_checkpoint(&mut out).unwrap();
+        drop(out);
+        fs::rename(&partial_path, &checkpoint_path).unwrap();
+    }
+
+    eprintln!("\nDone");
+}
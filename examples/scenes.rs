@@ -0,0 +1,67 @@
+//! Renders one of the preset scenes as a PPM on stdout:
+//!
+//!     cargo run --release --example scenes -- cornell_box [seed] [samples per pixel] > out.ppm
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use ray_tracing_in_one_week_rust::bvh::node::Node;
+use ray_tracing_in_one_week_rust::render::render_with_progress;
+use ray_tracing_in_one_week_rust::scenes::{self, Scene};
+use ray_tracing_in_one_week_rust::scheduler::CancellationToken;
+use std::env;
+use std::io::{stdout, BufWriter};
+use std::process;
+
+fn main() {
+    let name = env::args()
+        .nth(1)
+        .unwrap_or_else(|| "random_spheres".to_string());
+    let seed = env::args().nth(2).map_or(0, |s| s.parse().unwrap());
+
+    let scene: fn(u64) -> Scene = match name.as_str() {
+        "random_spheres" => scenes::random_spheres,
+        "bouncing_spheres" => scenes::bouncing_spheres,
+        "three_spheres" => scenes::three_spheres,
+        "cornell_box" => scenes::cornell_box,
+        "shader_balls" => scenes::shader_balls,
+        "texture_test" => scenes::texture_test,
+        "many_lights" => scenes::many_lights,
+        _ => {
+            eprintln!("unknown scene {}", name);
+            process::exit(2);
+        }
+    };
+    let Scene {
+        world,
+        camera,
+        environment,
+        mut settings,
+    } = scene(seed);
+    if let Some(samples) = env::args().nth(3) {
+        settings.samples_per_pixel = samples.parse().unwrap();
+    }
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let world = Node::new(&mut rng, &world.0, 0.0, 1.0).unwrap();
+
+    let image = render_with_progress(
+        &world,
+        &camera,
+        environment.as_ref(),
+        &settings,
+        &|progress| {
+            eprint!(
+                "\r{}/{} tiles, {:.1}s left   ",
+                progress.completed_tiles,
+                progress.total_tiles,
+                progress.eta.as_secs_f64()
+            )
+        },
+        &CancellationToken::new(),
+    )
+    .unwrap();
+
+    let mut out = BufWriter::new(stdout());
+    image.write_ppm(&mut out, &settings.tone_mapping).unwrap();
+    eprintln!("\nDone");
+}
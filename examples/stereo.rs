@@ -0,0 +1,46 @@
+use ray_tracing_in_one_week_rust::environment::gradient_sky::GradientSky;
+use ray_tracing_in_one_week_rust::render::RenderSettings;
+use ray_tracing_in_one_week_rust::scenes::three_spheres;
+use ray_tracing_in_one_week_rust::stereo::{compose, StereoLayout, StereoRig};
+use ray_tracing_in_one_week_rust::vector3::{Point3, Vector3};
+use std::env;
+use std::io::stdout;
+
+fn main() {
+    let layout = match env::args().nth(1).as_deref() {
+        Some("side-by-side") => StereoLayout::SideBySide,
+        Some("top-bottom") => StereoLayout::TopBottom,
+        _ => StereoLayout::Anaglyph,
+    };
+
+    // Image
+    let aspect_ratio = 3.0 / 2.0;
+    let image_width = 600usize;
+    let settings = RenderSettings::new(image_width, (image_width as f64 / aspect_ratio) as usize);
+
+    // World
+    let world = three_spheres(0).world;
+    let sky = GradientSky::default();
+
+    // Camera
+    let rig = StereoRig::new(
+        Point3::new(13.0, 2.0, 3.0),
+        Point3::zero(),
+        Vector3::new_y(1.0),
+        30.0,
+        aspect_ratio,
+        0.1,
+        0.3,
+        10.0,
+        0.0,
+        1.0,
+    );
+
+    // Render
+    let (left, right) = rig.render(&world, &sky, &settings);
+    compose(&left, &right, layout)
+        .write_ppm(&mut stdout().lock(), &settings.tone_mapping)
+        .unwrap();
+
+    eprintln!("\nDone");
+}
@@ -0,0 +1,54 @@
+use rand::thread_rng;
+use ray_tracing_in_one_week_rust::bvh::node::Node;
+use ray_tracing_in_one_week_rust::camera::Camera;
+use ray_tracing_in_one_week_rust::environment::gradient_sky::GradientSky;
+use ray_tracing_in_one_week_rust::render::{render, RenderSettings};
+use ray_tracing_in_one_week_rust::scenes::three_spheres;
+use ray_tracing_in_one_week_rust::telemetry;
+use ray_tracing_in_one_week_rust::vector3::{Point3, Vector3};
+use std::env;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+
+fn main() {
+    let out_dir = PathBuf::from(env::args().nth(1).unwrap_or_else(|| ".".to_string()));
+
+    // Image
+    let aspect_ratio = 3.0 / 2.0;
+    let image_width = 600usize;
+    let mut settings =
+        RenderSettings::new(image_width, (image_width as f64 / aspect_ratio) as usize);
+    settings.samples_per_pixel = 16;
+
+    // World
+    let world = telemetry::phase("build", || {
+        Node::new(&mut thread_rng(), &three_spheres(0).world.0, 0.0, 1.0).unwrap()
+    });
+    let sky = GradientSky::default();
+
+    // Camera
+    let camera = Camera::new(
+        Point3::new(13.0, 2.0, 3.0),
+        Point3::zero(),
+        Vector3::new_y(1.0),
+        30.0,
+        aspect_ratio,
+        0.1,
+        10.0,
+        0.0,
+        1.0,
+    );
+
+    // Render
+    let image = render(&world, &camera, &sky, &settings);
+
+    telemetry::phase("output", || {
+        let mut out = BufWriter::new(File::create(out_dir.join("telemetry.ppm")).unwrap());
+        image.write_ppm(&mut out, &settings.tone_mapping).unwrap();
+    });
+
+    let stats = telemetry::take();
+    eprint!("{}", stats);
+    std::fs::write(out_dir.join("telemetry.json"), stats.to_json()).unwrap();
+}
@@ -0,0 +1,42 @@
+use ray_tracing_in_one_week_rust::camera::Camera;
+use ray_tracing_in_one_week_rust::environment::gradient_sky::GradientSky;
+use ray_tracing_in_one_week_rust::render::RenderSettings;
+use ray_tracing_in_one_week_rust::scenes::three_spheres;
+use ray_tracing_in_one_week_rust::terminal_preview::{preview_settings, render_to_terminal};
+use ray_tracing_in_one_week_rust::vector3::{Point3, Vector3};
+use std::env;
+use std::io::stdout;
+
+fn main() {
+    // Fits the terminal if the shell exports its width
+    let columns = env::var("COLUMNS").map_or(80, |s| s.parse().unwrap());
+    let passes = env::args().nth(1).map_or(16, |s| s.parse().unwrap());
+
+    // Image
+    let aspect_ratio = 3.0 / 2.0;
+    let image_width = 600usize;
+    let mut settings =
+        RenderSettings::new(image_width, (image_width as f64 / aspect_ratio) as usize);
+    settings.samples_per_pixel = 2;
+
+    // World
+    let world = three_spheres(0).world;
+    let sky = GradientSky::default();
+
+    // Camera
+    let camera = Camera::new(
+        Point3::new(13.0, 2.0, 3.0),
+        Point3::zero(),
+        Vector3::new_y(1.0),
+        30.0,
+        aspect_ratio,
+        0.1,
+        10.0,
+        0.0,
+        1.0,
+    );
+
+    // Render
+    let settings = preview_settings(&settings, columns);
+    render_to_terminal(&world, &camera, &sky, &settings, passes, &mut stdout()).unwrap();
+}
@@ -0,0 +1,75 @@
+use ray_tracing_in_one_week_rust::camera::Camera;
+use ray_tracing_in_one_week_rust::environment::gradient_sky::GradientSky;
+use ray_tracing_in_one_week_rust::render::{render_with_progress, RenderSettings};
+use ray_tracing_in_one_week_rust::scenes::three_spheres;
+use ray_tracing_in_one_week_rust::scheduler::{CancellationToken, TileOrder};
+use ray_tracing_in_one_week_rust::vector3::{Point3, Vector3};
+use std::env;
+use std::io::{stdout, BufWriter};
+use std::thread;
+use std::time::Duration;
+
+fn main() {
+    // Gives up on the render after this many seconds, if given.
+    let time_limit = env::args().nth(1).map(|s| s.parse::<f64>().unwrap());
+
+    // Image
+    let aspect_ratio = 3.0 / 2.0;
+    let image_width = 600usize;
+    let mut settings =
+        RenderSettings::new(image_width, (image_width as f64 / aspect_ratio) as usize);
+    settings.tile_size = 32;
+    settings.tile_order = TileOrder::Hilbert;
+
+    // World
+    let world = three_spheres(0).world;
+    let sky = GradientSky::default();
+
+    // Camera
+    let camera = Camera::new(
+        Point3::new(13.0, 2.0, 3.0),
+        Point3::zero(),
+        Vector3::new_y(1.0),
+        30.0,
+        aspect_ratio,
+        0.1,
+        10.0,
+        0.0,
+        1.0,
+    );
+
+    // Render
+    let cancel = CancellationToken::new();
+    if let Some(seconds) = time_limit {
+        let cancel = cancel.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_secs_f64(seconds));
+            cancel.cancel();
+        });
+    }
+
+    let image = render_with_progress(
+        &world,
+        &camera,
+        &sky,
+        &settings,
+        &|progress| {
+            eprint!(
+                "\r{}/{} tiles, {:.1}s left   ",
+                progress.completed_tiles,
+                progress.total_tiles,
+                progress.eta.as_secs_f64()
+            )
+        },
+        &cancel,
+    );
+
+    match image {
+        Some(image) => {
+            let mut out = BufWriter::new(stdout());
+            image.write_ppm(&mut out, &settings.tone_mapping).unwrap();
+            eprintln!("\nDone");
+        }
+        None => eprintln!("\nCancelled"),
+    }
+}
@@ -0,0 +1,67 @@
+use rand::thread_rng;
+use ray_tracing_in_one_week_rust::bvh::node::Node;
+use ray_tracing_in_one_week_rust::camera::Camera;
+use ray_tracing_in_one_week_rust::render::RenderSettings;
+use ray_tracing_in_one_week_rust::scenes::random_spheres;
+use ray_tracing_in_one_week_rust::tone_mapping::{ToneMapOperator, ToneMapping, TransferFunction};
+use ray_tracing_in_one_week_rust::traversal_heatmap::{traversal_costs, TraversalCost};
+use ray_tracing_in_one_week_rust::vector3::{Point3, Vector3};
+use std::env;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+
+fn main() {
+    let out_dir = PathBuf::from(env::args().nth(1).unwrap_or_else(|| ".".to_string()));
+
+    // Image
+    let aspect_ratio = 3.0 / 2.0;
+    let image_width = 600usize;
+    let settings = RenderSettings::new(image_width, (image_width as f64 / aspect_ratio) as usize);
+    let mut rng = thread_rng();
+
+    // World
+    let world = random_spheres(0).world;
+    let world = Node::new(&mut rng, &world.0, 0.0, 1.0).unwrap();
+
+    // Camera
+    let camera = Camera::new(
+        Point3::new(13.0, 2.0, 3.0),
+        Point3::zero(),
+        Vector3::new_y(1.0),
+        20.0,
+        aspect_ratio,
+        0.1,
+        10.0,
+        0.0,
+        1.0,
+    );
+
+    // Render
+    let costs = traversal_costs(&world, &camera, &settings);
+
+    // The false colors are already display values
+    let tone_mapping = ToneMapping::new(ToneMapOperator::Clamp, TransferFunction::Linear);
+    for (name, cost) in [
+        ("aabb_tests", TraversalCost::AabbTests),
+        ("primitive_tests", TraversalCost::PrimitiveTests),
+    ] {
+        let max = costs.max(cost);
+        eprintln!(
+            "{}: mean {:.1}, legend ticks at {}, {}, {}, {}, {}",
+            name,
+            costs.mean(cost),
+            0,
+            max / 4,
+            max / 2,
+            max * 3 / 4,
+            max
+        );
+
+        let mut out = BufWriter::new(File::create(out_dir.join(format!("{}.ppm", name))).unwrap());
+        costs
+            .heatmap_with_legend(cost)
+            .write_ppm(&mut out, &tone_mapping)
+            .unwrap();
+    }
+}
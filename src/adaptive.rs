@@ -0,0 +1,157 @@
+use crate::false_color::false_color;
+use crate::image::Image;
+use crate::tone_mapping::luminance;
+use crate::vector3::Vector3;
+
+/// Keeps sampling a pixel in batches until the standard error of its mean luminance falls
+/// below `threshold` times the mean, or `max_samples` is reached.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AdaptiveSampling {
+    pub min_samples: usize,
+    pub max_samples: usize,
+    pub batch_size: usize,
+    pub threshold: f64,
+}
+
+impl Default for AdaptiveSampling {
+    fn default() -> Self {
+        AdaptiveSampling {
+            min_samples: 16,
+            max_samples: 1024,
+            batch_size: 16,
+            threshold: 0.02,
+        }
+    }
+}
+
+/// How many samples each pixel of a render received.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SampleCounts {
+    width: usize,
+    height: usize,
+    counts: Vec<usize>,
+}
+
+impl SampleCounts {
+    pub(crate) fn new(width: usize, height: usize, counts: Vec<usize>) -> Self {
+        assert_eq!(counts.len(), width * height);
+        SampleCounts {
+            width,
+            height,
+            counts,
+        }
+    }
+
+    pub fn counts(&self) -> &[usize] {
+        &self.counts
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> usize {
+        self.counts[y * self.width + x]
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.counts.iter().sum::<usize>() as f64 / self.counts.len().max(1) as f64
+    }
+
+    /// Colors each pixel by its sample count, from blue for the fewest to red for the most.
+    pub fn heatmap(&self) -> Image {
+        let min = self.counts.iter().copied().min().unwrap_or(0);
+        let max = self.counts.iter().copied().max().unwrap_or(0);
+        let range = (max - min).max(1) as f64;
+
+        Image::from_pixels(
+            self.width,
+            self.height,
+            self.counts
+                .iter()
+                .map(|c| false_color((c - min) as f64 / range))
+                .collect(),
+        )
+    }
+}
+
+/// Running mean and variance of a pixel's luminance, using Welford's algorithm.
+#[derive(Debug, Clone)]
+pub(crate) struct PixelEstimate {
+    n: usize,
+    mean: f64,
+    m2: f64,
+}
+
+impl PixelEstimate {
+    pub(crate) fn new() -> Self {
+        PixelEstimate {
+            n: 0,
+            mean: 0.0,
+            m2: 0.0,
+        }
+    }
+
+    pub(crate) fn add(&mut self, color: &Vector3) {
+        let l = luminance(color);
+        self.n += 1;
+        let delta = l - self.mean;
+        self.mean += delta / self.n as f64;
+        self.m2 += delta * (l - self.mean);
+    }
+
+    /// Combines the samples of two estimates of the same pixel, as in Chan et al.
+    pub(crate) fn merge(&mut self, other: &PixelEstimate) {
+        let n = self.n + other.n;
+        if n == 0 {
+            return;
+        }
+        let delta = other.mean - self.mean;
+        self.mean += delta * other.n as f64 / n as f64;
+        self.m2 += other.m2 + delta * delta * (self.n * other.n) as f64 / n as f64;
+        self.n = n;
+    }
+
+    pub(crate) fn count(&self) -> usize {
+        self.n
+    }
+
+    /// The sample count, mean and sum of squared deviations, for saving the estimate.
+    pub(crate) fn parts(&self) -> (usize, f64, f64) {
+        (self.n, self.mean, self.m2)
+    }
+
+    pub(crate) fn from_parts((n, mean, m2): (usize, f64, f64)) -> Self {
+        PixelEstimate { n, mean, m2 }
+    }
+
+    /// Standard error of the mean relative to the mean, floored so black pixels converge.
+    pub(crate) fn relative_error(&self) -> f64 {
+        if self.n < 2 {
+            return f64::INFINITY;
+        }
+        let variance = self.m2 / (self.n - 1) as f64;
+        f64::sqrt(variance / self.n as f64) / self.mean.abs().max(1e-2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn constant_pixels_have_no_error() {
+        let mut estimate = PixelEstimate::new();
+        for _ in 0..4 {
+            estimate.add(&Vector3::one());
+        }
+
+        assert_eq!(estimate.relative_error(), 0.0);
+    }
+
+    #[test]
+    fn noisy_pixels_have_error() {
+        let mut estimate = PixelEstimate::new();
+        for i in 0..4 {
+            estimate.add(&(Vector3::one() * (i % 2) as f64));
+        }
+
+        assert!((estimate.relative_error() - f64::sqrt(1.0 / 12.0) / 0.5).abs() < 1e-12);
+    }
+}
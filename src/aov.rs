@@ -0,0 +1,122 @@
+use crate::environment::environment::Environment;
+use crate::hit::HitRecord;
+use crate::image::Image;
+use crate::ray::Ray;
+use crate::vector3::{Color, Vector3};
+
+/// Arbitrary output variables: per-pixel data about what the camera rays hit first, for
+/// compositing and denoising. Each one is averaged over the pixel's samples, except the object
+/// index, which comes from the first sample.
+#[derive(Debug, Clone)]
+pub struct Aovs {
+    /// World-space normal facing the camera, or zero where rays escaped.
+    pub normal: Image,
+    /// Distance from the ray origin to the hit, the same in every channel, or infinity where
+    /// every sample escaped.
+    pub depth: Image,
+    /// Texture coordinates in the red and green channels.
+    pub uv: Image,
+    /// Material albedo, or the environment where rays escaped.
+    pub albedo: Image,
+    /// Index into the world's object list, the same in every channel, or -1 where rays escaped.
+    pub object_id: Image,
+}
+
+impl Aovs {
+    /// Each image paired with a short name suitable for a file name.
+    pub fn images(&self) -> [(&'static str, &Image); 5] {
+        [
+            ("normal", &self.normal),
+            ("depth", &self.depth),
+            ("uv", &self.uv),
+            ("albedo", &self.albedo),
+            ("object_id", &self.object_id),
+        ]
+    }
+
+    pub(crate) fn from_pixels(width: usize, height: usize, pixels: Vec<AovPixel>) -> Self {
+        let channel = |f: fn(&AovPixel) -> Color| {
+            Image::from_pixels(width, height, pixels.iter().map(f).collect())
+        };
+
+        Aovs {
+            normal: channel(|p| p.normal.clone()),
+            depth: channel(|p| Color::new_all(p.depth)),
+            uv: channel(|p| p.uv.clone()),
+            albedo: channel(|p| p.albedo.clone()),
+            object_id: channel(|p| Color::new_all(p.object_id)),
+        }
+    }
+}
+
+pub(crate) struct AovPixel {
+    normal: Color,
+    depth: f64,
+    uv: Color,
+    albedo: Color,
+    object_id: f64,
+}
+
+/// Sums the first hit of each camera sample for one pixel.
+pub(crate) struct AovAccumulator {
+    normal: Vector3,
+    uv: Vector3,
+    albedo: Vector3,
+    depth: f64,
+    samples: usize,
+    hits: usize,
+    object_id: Option<f64>,
+}
+
+impl AovAccumulator {
+    pub(crate) fn new() -> Self {
+        AovAccumulator {
+            normal: Vector3::zero(),
+            uv: Vector3::zero(),
+            albedo: Vector3::zero(),
+            depth: 0.0,
+            samples: 0,
+            hits: 0,
+            object_id: None,
+        }
+    }
+
+    pub(crate) fn add(
+        &mut self,
+        ray: &Ray,
+        record: Option<&HitRecord>,
+        environment: &dyn Environment,
+    ) {
+        self.samples += 1;
+        match record {
+            Some(r) => {
+                self.hits += 1;
+                self.normal += r.normal().clone();
+                self.uv += Vector3::new(r.u(), r.v(), 0.0);
+                self.albedo += Vector3::from(r.material().albedo(r));
+                self.depth += r.t() * ray.direction().length();
+                self.object_id
+                    .get_or_insert(r.object_index().map(|i| i as f64).unwrap_or(-1.0));
+            }
+            None => {
+                self.albedo += Vector3::from(environment.value(ray.direction()));
+                self.object_id.get_or_insert(-1.0);
+            }
+        }
+    }
+
+    pub(crate) fn finish(&self) -> AovPixel {
+        let n = self.samples.max(1) as f64;
+        AovPixel {
+            normal: Color::from(&self.normal / n),
+            depth: if self.hits > 0 {
+                self.depth / self.hits as f64
+            } else {
+                f64::INFINITY
+            },
+            uv: Color::from(&self.uv / n),
+            albedo: Color::from(&self.albedo / n),
+            object_id: self.object_id.unwrap_or(-1.0),
+        }
+    }
+}
@@ -1,8 +1,6 @@
 use crate::ray::Ray;
+use crate::telemetry::{self, Counter};
 use crate::vector3::{Point3, Vector3};
-use std::cmp::min;
-use std::mem::swap;
-use std::ops::{Div, Sub};
 
 #[derive(Debug, Clone)]
 pub struct AABB {
@@ -25,6 +23,7 @@ impl AABB {
     }
 
     pub(crate) fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> bool {
+        telemetry::count(Counter::AabbTest);
         let mut t_min = t_min;
         let mut t_max = t_max;
 
@@ -50,6 +49,31 @@ impl AABB {
         true
     }
 
+    /// The squared distance from `point` to the box, 0 inside it.
+    pub fn nearest_squared(&self, point: &Point3) -> f64 {
+        (0..3)
+            .map(|a| {
+                let p = point.element(a);
+                let d = f64::max(self.minimum.element(a) - p, p - self.maximum.element(a)).max(0.0);
+                d * d
+            })
+            .sum()
+    }
+
+    /// The squared distance from `point` to the farthest corner of the box.
+    pub fn farest_squared(&self, point: &Point3) -> f64 {
+        (0..3)
+            .map(|a| {
+                let p = point.element(a);
+                let d = f64::max(
+                    (p - self.minimum.element(a)).abs(),
+                    (p - self.maximum.element(a)).abs(),
+                );
+                d * d
+            })
+            .sum()
+    }
+
     pub fn surrounding_box(&self, other: &Self) -> Self {
         let small = Vector3::new_from_iter(
             self.minimum
@@ -67,3 +91,21 @@ impl AABB {
         AABB::new(Point3::from(small), Point3::from(big))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distances_to_faces_and_corners() {
+        let bbox = AABB::new(Point3::zero(), Point3::new(1.0, 2.0, 3.0));
+
+        let outside = Point3::new(2.0, 1.0, -1.0);
+        assert_eq!(bbox.nearest_squared(&outside), 2.0);
+        assert_eq!(bbox.farest_squared(&outside), 4.0 + 1.0 + 16.0);
+
+        let inside = Point3::new(0.5, 1.0, 1.5);
+        assert_eq!(bbox.nearest_squared(&inside), 0.0);
+        assert_eq!(bbox.farest_squared(&inside), 0.25 + 1.0 + 2.25);
+    }
+}
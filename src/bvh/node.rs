@@ -2,41 +2,101 @@ use crate::bvh::aabb::AABB;
 use crate::hit::{Hit, HitRecord};
 use crate::hit_objects::HitObject;
 use crate::ray::Ray;
+use crate::telemetry::{self, Counter};
 use crate::vector3::Point3;
 use rand::{Rng, RngCore};
 use std::cmp::Ordering;
-use std::sync::Arc;
 
 #[derive(Debug)]
 pub enum Tree {
-    Leaf(HitObject),
+    /// An object and its index in the slice the tree was built from.
+    Leaf(usize, HitObject),
     Node(Box<Node>),
 }
 
 impl Hit for Tree {
     fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
         match self {
-            Self::Leaf(ho) => ho.hit(ray, t_min, t_max),
+            Self::Leaf(i, ho) => ho.hit(ray, t_min, t_max).map(|r| r.with_object_index(*i)),
             Self::Node(node) => node.hit(ray, t_min, t_max),
         }
     }
 
     fn bounding_box(&self, time0: f64, time1: f64) -> Option<AABB> {
         match self {
-            Self::Leaf(ho) => ho.bounding_box(time0, time1),
+            Self::Leaf(_, ho) => ho.bounding_box(time0, time1),
             Self::Node(node) => node.bounding_box(time0, time1),
         }
     }
 
     fn nearest_squared(&self, point: &Point3) -> f64 {
-        unimplemented!()
+        self.nearest_below(point, f64::INFINITY)
     }
 
     fn farest_squared(&self, point: &Point3) -> f64 {
-        unimplemented!()
+        self.farthest_above(point, 0.0)
     }
 }
 
+impl Tree {
+    /// At most the squared distance from `point` to anything in the tree at `time`.
+    fn lower_bound(&self, point: &Point3, time: f64) -> f64 {
+        match self {
+            Self::Leaf(_, ho) => ho
+                .bounding_box(time, time)
+                .map_or(0.0, |b| b.nearest_squared(point)),
+            Self::Node(node) => node.bbox.nearest_squared(point),
+        }
+    }
+
+    fn search(
+        &self,
+        point: &Point3,
+        time: f64,
+        bound: &mut f64,
+        found: &mut dyn FnMut(ClosestPoint) -> f64,
+    ) {
+        match self {
+            Self::Leaf(i, ho) => {
+                let closest = ho.closest_point(point, time);
+                let distance = (point - &closest).length();
+                if distance * distance <= *bound {
+                    *bound = found(ClosestPoint {
+                        object_index: *i,
+                        point: closest,
+                        distance,
+                    });
+                }
+            }
+            Self::Node(node) => node.search(point, time, bound, found),
+        }
+    }
+
+    fn nearest_below(&self, point: &Point3, best: f64) -> f64 {
+        match self {
+            Self::Leaf(_, ho) => best.min(ho.nearest_squared(point)),
+            Self::Node(node) => node.nearest_below(point, best),
+        }
+    }
+
+    fn farthest_above(&self, point: &Point3, best: f64) -> f64 {
+        match self {
+            Self::Leaf(_, ho) => best.max(ho.farest_squared(point)),
+            Self::Node(node) => node.farthest_above(point, best),
+        }
+    }
+}
+
+/// The point of an object's surface closest to a query point.
+#[derive(Debug, Clone)]
+pub struct ClosestPoint {
+    /// Index into the slice the tree was built from.
+    pub object_index: usize,
+    pub point: Point3,
+    /// From the query point to `point`, also when the query point is inside the object.
+    pub distance: f64,
+}
+
 #[derive(Debug)]
 pub struct Node {
     left: Tree,
@@ -44,9 +104,9 @@ pub struct Node {
     bbox: AABB,
 }
 
-fn bbox_compare(axis: usize, a: &HitObject, b: &HitObject) -> Ordering {
-    let a_bbox = a.bounding_box(0.0, 0.0).unwrap();
-    let b_bbox = b.bounding_box(0.0, 0.0).unwrap();
+fn bbox_compare(axis: usize, a: &(usize, HitObject), b: &(usize, HitObject)) -> Ordering {
+    let a_bbox = a.1.bounding_box(0.0, 0.0).unwrap();
+    let b_bbox = b.1.bounding_box(0.0, 0.0).unwrap();
 
     a_bbox
         .minimum()
@@ -55,6 +115,10 @@ fn bbox_compare(axis: usize, a: &HitObject, b: &HitObject) -> Ordering {
         .unwrap()
 }
 
+fn leaf(object: &(usize, HitObject)) -> Tree {
+    Tree::Leaf(object.0, object.1.clone())
+}
+
 impl Node {
     pub fn new<R: RngCore>(
         rng: &mut R,
@@ -62,13 +126,13 @@ impl Node {
         time0: f64,
         time1: f64,
     ) -> Option<Self> {
-        let mut objects = src_objects.to_vec();
+        let mut objects: Vec<_> = src_objects.iter().cloned().enumerate().collect();
         Self::new_inner(rng, &mut objects, time0, time1)
     }
 
     fn new_inner<R: RngCore>(
         rng: &mut R,
-        objects: &mut [HitObject],
+        objects: &mut [(usize, HitObject)],
         time0: f64,
         time1: f64,
     ) -> Option<Self> {
@@ -78,18 +142,12 @@ impl Node {
 
         let (left, right) = match object_span {
             0 => unreachable!(),
-            1 => (Tree::Leaf(objects.first().unwrap().clone()), None),
+            1 => (leaf(&objects[0]), None),
             2 => {
                 if bbox_compare(axis, &objects[0], &objects[1]) == Ordering::Less {
-                    (
-                        Tree::Leaf(objects[0].clone()),
-                        Some(Tree::Leaf(objects[1].clone())),
-                    )
+                    (leaf(&objects[0]), Some(leaf(&objects[1])))
                 } else {
-                    (
-                        Tree::Leaf(objects[1].clone()),
-                        Some(Tree::Leaf(objects[0].clone())),
-                    )
+                    (leaf(&objects[1]), Some(leaf(&objects[0])))
                 }
             }
             _ => {
@@ -126,20 +184,96 @@ impl Node {
         })
     }
 
+    // A node always holds at least one object, so there is no is_empty to go with it
+    #[allow(clippy::len_without_is_empty)]
     pub fn len(&self) -> usize {
         (match &self.left {
-            Tree::Leaf(_) => 1,
+            Tree::Leaf(..) => 1,
             Tree::Node(n) => n.len(),
         }) + (match &self.right {
-            Some(Tree::Leaf(_)) => 1,
+            Some(Tree::Leaf(..)) => 1,
             Some(Tree::Node(n)) => n.len(),
             None => 0,
         })
     }
+
+    /// The surface nearest to `point` at `time`, which should lie in the interval the tree was
+    /// built for. `None` if no distance is a number, as for a NaN point.
+    pub fn closest_point(&self, point: &Point3, time: f64) -> Option<ClosestPoint> {
+        let mut best = None;
+        let mut bound = f64::INFINITY;
+        self.search(point, time, &mut bound, &mut |candidate| {
+            let bound = candidate.distance * candidate.distance;
+            best = Some(candidate);
+            bound
+        });
+        best
+    }
+
+    /// Every object whose surface comes within `radius` of `point` at `time`, nearest first.
+    pub fn within_radius(&self, point: &Point3, radius: f64, time: f64) -> Vec<ClosestPoint> {
+        let bound = radius * radius;
+        let mut found = Vec::new();
+        self.search(point, time, &mut bound.clone(), &mut |candidate| {
+            found.push(candidate);
+            bound
+        });
+        found.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+        found
+    }
+
+    /// Reports each object whose surface is within the square root of `bound`, which `found`
+    /// then sets. Children are visited nearest box first and skipped when their boxes are out
+    /// of bounds.
+    fn search(
+        &self,
+        point: &Point3,
+        time: f64,
+        bound: &mut f64,
+        found: &mut dyn FnMut(ClosestPoint) -> f64,
+    ) {
+        let left = (self.left.lower_bound(point, time), &self.left);
+        let right = self.right.as_ref().map(|r| (r.lower_bound(point, time), r));
+        let (first, second) = match right {
+            Some(right) if right.0 < left.0 => (right, Some(left)),
+            right => (left, right),
+        };
+
+        for (lower_bound, tree) in std::iter::once(first).chain(second) {
+            if lower_bound <= *bound {
+                tree.search(point, time, bound, found);
+            }
+        }
+    }
+
+    /// The smaller of `best` and the nearest squared distance to the objects, skipping subtrees
+    /// whose boxes are no nearer than `best`.
+    fn nearest_below(&self, point: &Point3, best: f64) -> f64 {
+        if self.bbox.nearest_squared(point) >= best {
+            return best;
+        }
+        let best = self.left.nearest_below(point, best);
+        match &self.right {
+            Some(right) => right.nearest_below(point, best),
+            None => best,
+        }
+    }
+
+    fn farthest_above(&self, point: &Point3, best: f64) -> f64 {
+        if self.bbox.farest_squared(point) <= best {
+            return best;
+        }
+        let best = self.left.farthest_above(point, best);
+        match &self.right {
+            Some(right) => right.farthest_above(point, best),
+            None => best,
+        }
+    }
 }
 
 impl Hit for Node {
     fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        telemetry::count(Counter::BvhNodeVisit);
         if !self.bbox.hit(ray, t_min, t_max) {
             return None;
         }
@@ -160,11 +294,104 @@ impl Hit for Node {
         Some(self.bbox.clone())
     }
 
+    /// Prunes with the boxes the tree was built with, so it assumes moving objects stay within
+    /// them, as they do when the tree was built for their whole motion.
     fn nearest_squared(&self, point: &Point3) -> f64 {
-        unimplemented!()
+        self.nearest_below(point, f64::INFINITY)
     }
 
     fn farest_squared(&self, point: &Point3) -> f64 {
-        unimplemented!()
+        self.farthest_above(point, 0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hit_objects::HitObjects;
+    use crate::scenes::bouncing_spheres;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn trees_match_the_objects_they_hold() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let world = bouncing_spheres(5).world;
+        let node = Node::new(&mut rng, &world.0, 0.0, 1.0).unwrap();
+        let tree = Tree::Node(Box::new(
+            Node::new(&mut rng, &world.0[..3], 0.0, 1.0).unwrap(),
+        ));
+        let three = HitObjects(world.0[..3].to_vec());
+        let leaf = Tree::Leaf(0, world.0[7].clone());
+
+        for _ in 0..20 {
+            let point = Point3::new(
+                rng.gen_range(-15.0..15.0),
+                rng.gen_range(-2.0..5.0),
+                rng.gen_range(-15.0..15.0),
+            );
+            assert_eq!(node.nearest_squared(&point), world.nearest_squared(&point));
+            assert_eq!(node.farest_squared(&point), world.farest_squared(&point));
+            assert_eq!(tree.nearest_squared(&point), three.nearest_squared(&point));
+            assert_eq!(tree.farest_squared(&point), three.farest_squared(&point));
+            assert_eq!(
+                leaf.nearest_squared(&point),
+                world.0[7].nearest_squared(&point)
+            );
+        }
+    }
+
+    #[test]
+    fn point_queries_match_a_brute_force_search() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let world = bouncing_spheres(5).world;
+        let node = Node::new(&mut rng, &world.0, 0.0, 1.0).unwrap();
+        let time = 0.5;
+
+        for _ in 0..20 {
+            let point = Point3::new(
+                rng.gen_range(-15.0..15.0),
+                rng.gen_range(-2.0..5.0),
+                rng.gen_range(-15.0..15.0),
+            );
+            let mut distances: Vec<(usize, f64)> = world
+                .0
+                .iter()
+                .map(|ho| (&point - &ho.closest_point(&point, time)).length())
+                .enumerate()
+                .collect();
+            distances.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+            let closest = node.closest_point(&point, time).unwrap();
+            assert_eq!(closest.object_index, distances[0].0);
+            assert_eq!(closest.distance, distances[0].1);
+
+            let radius = 2.0;
+            let within: Vec<usize> = node
+                .within_radius(&point, radius, time)
+                .iter()
+                .map(|c| c.object_index)
+                .collect();
+            let expected: Vec<usize> = distances
+                .iter()
+                .take_while(|(_, d)| *d <= radius)
+                .map(|(i, _)| *i)
+                .collect();
+            assert_eq!(within, expected);
+        }
+    }
+
+    #[test]
+    fn point_queries_skip_nan_points() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let world = bouncing_spheres(5).world;
+        let node = Node::new(&mut rng, &world.0, 0.0, 1.0).unwrap();
+
+        let point = Point3::new(f64::NAN, 0.0, 0.0);
+        assert!(node.closest_point(&point, 0.5).is_none());
+        assert!(node.within_radius(&point, 2.0, 0.5).is_empty());
+        assert!(node
+            .within_radius(&Point3::zero(), f64::NAN, 0.5)
+            .is_empty());
     }
 }
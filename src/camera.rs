@@ -2,16 +2,41 @@ use crate::degrees_to_radians;
 use crate::ray::Ray;
 use crate::vector3::{Point3, Vector3};
 use rand::{Rng, RngCore};
+use std::f64::consts::PI;
 
+/// How a camera maps image coordinates `(s, t)` in `[0, 1]²` onto rays.
+#[derive(Debug, Clone)]
+pub enum Projection {
+    /// Thin-lens perspective with depth of field, as built in the book.
+    Perspective {
+        lower_left_corner: Point3,
+        horizontal: Vector3,
+        vertical: Vector3,
+        lens_radius: f64,
+    },
+    /// Parallel rays leaving a `horizontal` by `vertical` rectangle centered on the camera.
+    Orthographic {
+        lower_left_corner: Point3,
+        horizontal: Vector3,
+        vertical: Vector3,
+    },
+    /// Equidistant fisheye: the angle from the view axis grows linearly with the distance from
+    /// the image center, reaching half of `field_of_view` at the top and bottom edges.
+    Fisheye {
+        field_of_view: f64,
+        aspect_ratio: f64,
+    },
+    /// Full 360° by 180° latitude-longitude panorama, centered on the view direction.
+    Equirectangular,
+}
+
+#[derive(Debug, Clone)]
 pub struct Camera {
     origin: Point3,
-    lower_left_corner: Point3,
-    horizontal: Vector3,
-    vertical: Vector3,
     u: Vector3,
     v: Vector3,
     w: Vector3,
-    lens_radius: f64,
+    projection: Projection,
     time0: f64,
     time1: f64,
 }
@@ -20,9 +45,28 @@ impl Camera {
     pub fn origin(&self) -> &Point3 {
         &self.origin
     }
+    pub fn u(&self) -> &Vector3 {
+        &self.u
+    }
+    pub fn v(&self) -> &Vector3 {
+        &self.v
+    }
+    pub fn w(&self) -> &Vector3 {
+        &self.w
+    }
+    pub fn projection(&self) -> &Projection {
+        &self.projection
+    }
+    pub fn time0(&self) -> f64 {
+        self.time0
+    }
+    pub fn time1(&self) -> f64 {
+        self.time1
+    }
 }
 
 impl Camera {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         look_from: Point3,
         look_at: Point3,
@@ -39,9 +83,7 @@ impl Camera {
         let viewport_height = 2.0 * h;
         let viewport_width = aspect_ratio * viewport_height;
 
-        let w = (&look_from - &look_at).unit_vector();
-        let u = up_vector.cross(&w).unit_vector();
-        let v = w.cross(&u);
+        let (u, v, w) = basis(&look_from, &look_at, &up_vector);
 
         let origin = look_from;
         let horizontal = &u * (viewport_width * focus_dist);
@@ -52,34 +94,185 @@ impl Camera {
 
         Self {
             origin,
-            lower_left_corner,
-            horizontal,
-            vertical,
             u,
             v,
             w,
-            lens_radius,
+            projection: Projection::Perspective {
+                lower_left_corner,
+                horizontal,
+                vertical,
+                lens_radius,
+            },
             time0,
             time1,
         }
     }
 
+    /// `viewport_height` is the height, in world units, of the region that fills the image.
+    pub fn new_orthographic(
+        look_from: Point3,
+        look_at: Point3,
+        up_vector: Vector3,
+        viewport_height: f64,
+        aspect_ratio: f64,
+        time0: f64,
+        time1: f64,
+    ) -> Self {
+        let (u, v, w) = basis(&look_from, &look_at, &up_vector);
+
+        let origin = look_from;
+        let horizontal = &u * (aspect_ratio * viewport_height);
+        let vertical = &v * viewport_height;
+        let lower_left_corner = origin.clone() - &horizontal / 2.0 - &vertical / 2.0;
+
+        Self {
+            origin,
+            u,
+            v,
+            w,
+            projection: Projection::Orthographic {
+                lower_left_corner,
+                horizontal,
+                vertical,
+            },
+            time0,
+            time1,
+        }
+    }
+
+    /// `field_of_view` is in degrees and may exceed 180.
+    pub fn new_fisheye(
+        look_from: Point3,
+        look_at: Point3,
+        up_vector: Vector3,
+        field_of_view: f64,
+        aspect_ratio: f64,
+        time0: f64,
+        time1: f64,
+    ) -> Self {
+        let (u, v, w) = basis(&look_from, &look_at, &up_vector);
+
+        Self {
+            origin: look_from,
+            u,
+            v,
+            w,
+            projection: Projection::Fisheye {
+                field_of_view: degrees_to_radians(field_of_view),
+                aspect_ratio,
+            },
+            time0,
+            time1,
+        }
+    }
+
+    /// The image should have a 2:1 aspect ratio to keep pixels square.
+    pub fn new_equirectangular(
+        look_from: Point3,
+        look_at: Point3,
+        up_vector: Vector3,
+        time0: f64,
+        time1: f64,
+    ) -> Self {
+        let (u, v, w) = basis(&look_from, &look_at, &up_vector);
+
+        Self {
+            origin: look_from,
+            u,
+            v,
+            w,
+            projection: Projection::Equirectangular,
+            time0,
+            time1,
+        }
+    }
+
+    /// Moves the eye point by `offset` without moving the perspective image window, which gives
+    /// the off-axis frustum a stereo eye needs. Orthographic windows move along with the eye.
+    pub fn shifted(&self, offset: &Vector3) -> Self {
+        let mut camera = self.clone();
+        camera.origin = &self.origin + offset;
+        if let Projection::Orthographic {
+            lower_left_corner, ..
+        } = &mut camera.projection
+        {
+            *lower_left_corner = &*lower_left_corner + offset;
+        }
+        camera
+    }
+
     pub fn ray<R: RngCore>(&self, rng: &mut R, s: f64, t: f64) -> Ray {
-        let rd = Vector3::random_in_unit_disk(rng) * self.lens_radius;
-        let offset = &self.u * rd.x() + &self.v * rd.y();
-        let hv = &self.horizontal * s;
-        let vv = &self.vertical * t;
-
-        Ray::new(
-            &self.origin + &offset,
-            &Vector3::from(self.lower_left_corner.clone()) + &hv + vv
-                - Vector3::from(self.origin.clone())
-                - offset,
-            rng.gen_range(self.time0..self.time1),
-        )
+        self.ray_from_sample(s, t, (rng.gen(), rng.gen()), rng.gen())
+    }
+
+    /// Like [`ray`](Self::ray), with the point on the lens and the time inside the shutter
+    /// interval picked by `lens` in `[0, 1)²` and `time` in `[0, 1)`.
+    pub fn ray_from_sample(&self, s: f64, t: f64, lens: (f64, f64), time: f64) -> Ray {
+        let time = self.time0 + (self.time1 - self.time0) * time;
+
+        match &self.projection {
+            Projection::Perspective {
+                lower_left_corner,
+                horizontal,
+                vertical,
+                lens_radius,
+            } => {
+                let rd = Vector3::in_unit_disk_from_square(lens.0, lens.1) * *lens_radius;
+                let offset = &self.u * rd.x() + &self.v * rd.y();
+                let hv = horizontal * s;
+                let vv = vertical * t;
+
+                Ray::new(
+                    &self.origin + &offset,
+                    &Vector3::from(lower_left_corner.clone()) + &hv + vv
+                        - Vector3::from(self.origin.clone())
+                        - offset,
+                    time,
+                )
+            }
+            Projection::Orthographic {
+                lower_left_corner,
+                horizontal,
+                vertical,
+            } => Ray::new(
+                lower_left_corner + &(horizontal * s + vertical * t),
+                -&self.w,
+                time,
+            ),
+            Projection::Fisheye {
+                field_of_view,
+                aspect_ratio,
+            } => {
+                let x = (2.0 * s - 1.0) * aspect_ratio;
+                let y = 2.0 * t - 1.0;
+                let r = f64::sqrt(x * x + y * y);
+                let theta = r * field_of_view / 2.0;
+                let phi = f64::atan2(y, x);
+
+                let direction = (&self.u * phi.cos() + &self.v * phi.sin()) * theta.sin()
+                    - &self.w * theta.cos();
+                Ray::new(self.origin.clone(), direction, time)
+            }
+            Projection::Equirectangular => {
+                let phi = (s - 0.5) * 2.0 * PI;
+                let theta = (t - 0.5) * PI;
+
+                let direction = &self.u * (theta.cos() * phi.sin()) + &self.v * theta.sin()
+                    - &self.w * (theta.cos() * phi.cos());
+                Ray::new(self.origin.clone(), direction, time)
+            }
+        }
     }
 }
 
+fn basis(look_from: &Point3, look_at: &Point3, up_vector: &Vector3) -> (Vector3, Vector3, Vector3) {
+    let w = (look_from - look_at).unit_vector();
+    let u = up_vector.cross(&w).unit_vector();
+    let v = w.cross(&u);
+
+    (u, v, w)
+}
+
 impl Default for Camera {
     fn default() -> Self {
         Self::new(
@@ -95,3 +288,85 @@ impl Default for Camera {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::thread_rng;
+
+    fn assert_direction(ray: &Ray, expected: Vector3) {
+        let d = ray.direction().unit_vector() - expected;
+        assert!(d.length() < 1e-9, "{:?}", ray.direction());
+    }
+
+    #[test]
+    fn projections_look_forward_at_the_image_center() {
+        let mut rng = thread_rng();
+        let forward = Vector3::new_z(-1.0);
+        let cameras = [
+            Camera::new_orthographic(
+                Point3::zero(),
+                Point3::new_z(-1.0),
+                Vector3::new_y(1.0),
+                2.0,
+                1.0,
+                0.0,
+                1.0,
+            ),
+            Camera::new_fisheye(
+                Point3::zero(),
+                Point3::new_z(-1.0),
+                Vector3::new_y(1.0),
+                180.0,
+                1.0,
+                0.0,
+                1.0,
+            ),
+            Camera::new_equirectangular(
+                Point3::zero(),
+                Point3::new_z(-1.0),
+                Vector3::new_y(1.0),
+                0.0,
+                1.0,
+            ),
+        ];
+
+        for camera in &cameras {
+            let ray = camera.ray(&mut rng, 0.5, 0.5);
+            assert_direction(&ray, forward.clone());
+            assert!((0.0..1.0).contains(&ray.time()));
+        }
+    }
+
+    #[test]
+    fn equirectangular_covers_the_full_sphere() {
+        let mut rng = thread_rng();
+        let camera = Camera::new_equirectangular(
+            Point3::zero(),
+            Point3::new_z(-1.0),
+            Vector3::new_y(1.0),
+            0.0,
+            0.0,
+        );
+
+        assert_direction(&camera.ray(&mut rng, 0.0, 0.5), Vector3::new_z(1.0));
+        assert_direction(&camera.ray(&mut rng, 0.75, 0.5), Vector3::new_x(1.0));
+        assert_direction(&camera.ray(&mut rng, 0.5, 1.0), Vector3::new_y(1.0));
+    }
+
+    #[test]
+    fn fisheye_edge_reaches_half_field_of_view() {
+        let mut rng = thread_rng();
+        let camera = Camera::new_fisheye(
+            Point3::zero(),
+            Point3::new_z(-1.0),
+            Vector3::new_y(1.0),
+            180.0,
+            1.0,
+            0.0,
+            0.0,
+        );
+
+        assert_direction(&camera.ray(&mut rng, 0.5, 1.0), Vector3::new_y(1.0));
+    }
+}
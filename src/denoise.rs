@@ -0,0 +1,234 @@
+use crate::aov::Aovs;
+use crate::image::Image;
+use crate::vector3::{Color, Vector3};
+use rayon::prelude::*;
+
+const KERNEL: [f64; 5] = [1.0 / 16.0, 1.0 / 4.0, 3.0 / 8.0, 1.0 / 4.0, 1.0 / 16.0];
+const ALBEDO_EPS: f64 = 1e-3;
+
+/// Parameters of the edge-avoiding À-trous filter from Dammertz et al., "Edge-Avoiding À-Trous
+/// Wavelet Transform for fast Global Illumination Filtering" (2010). Each `sigma` sets how much
+/// a difference in that guide is tolerated before neighbours stop being averaged in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DenoiseSettings {
+    /// Number of wavelet passes; the filter footprint doubles with each one.
+    pub iterations: usize,
+    pub sigma_color: f64,
+    pub sigma_normal: f64,
+    /// Relative to the depth of the center pixel.
+    pub sigma_depth: f64,
+    pub sigma_albedo: f64,
+}
+
+impl Default for DenoiseSettings {
+    fn default() -> Self {
+        DenoiseSettings {
+            iterations: 5,
+            sigma_color: 1.0,
+            sigma_normal: 0.3,
+            sigma_depth: 0.05,
+            sigma_albedo: 0.1,
+        }
+    }
+}
+
+/// Filters `beauty` guided by the albedo, normal and depth in `aovs`. The beauty image is divided
+/// by albedo before filtering and multiplied back afterwards, so texture detail is kept.
+pub fn denoise(beauty: &Image, aovs: &Aovs, settings: &DenoiseSettings) -> Image {
+    let width = beauty.width();
+    let height = beauty.height();
+
+    let mut illumination: Vec<Vector3> = beauty
+        .pixels()
+        .iter()
+        .zip(aovs.albedo.pixels())
+        .map(|(c, a)| demodulate(&Vector3::from(c.clone()), &Vector3::from(a.clone())))
+        .collect();
+
+    for i in 0..settings.iterations {
+        let step = 1usize << i;
+        let sigma_color = settings.sigma_color / (1u32 << i) as f64;
+
+        illumination = (0..height)
+            .into_par_iter()
+            .flat_map_iter(|y| {
+                let illumination = &illumination;
+                (0..width).map(move |x| {
+                    filter_pixel(
+                        illumination,
+                        aovs,
+                        settings,
+                        sigma_color,
+                        step,
+                        (x, y),
+                        (width, height),
+                    )
+                })
+            })
+            .collect();
+    }
+
+    Image::from_pixels(
+        width,
+        height,
+        illumination
+            .iter()
+            .zip(aovs.albedo.pixels())
+            .map(|(l, a)| Color::from(remodulate(l, &Vector3::from(a.clone()))))
+            .collect(),
+    )
+}
+
+fn filter_pixel(
+    illumination: &[Vector3],
+    aovs: &Aovs,
+    settings: &DenoiseSettings,
+    sigma_color: f64,
+    step: usize,
+    (x, y): (usize, usize),
+    (width, height): (usize, usize),
+) -> Vector3 {
+    let p = y * width + x;
+    let color_p = &illumination[p];
+    let normal_p = Vector3::from(aovs.normal.pixels()[p].clone());
+    let albedo_p = Vector3::from(aovs.albedo.pixels()[p].clone());
+    let depth_p = aovs.depth.pixels()[p].r();
+
+    let mut sum = Vector3::zero();
+    let mut weight_sum = 0.0;
+
+    for (dy, ky) in KERNEL.iter().enumerate() {
+        let qy = y as isize + (dy as isize - 2) * step as isize;
+        if qy < 0 || qy >= height as isize {
+            continue;
+        }
+        for (dx, kx) in KERNEL.iter().enumerate() {
+            let qx = x as isize + (dx as isize - 2) * step as isize;
+            if qx < 0 || qx >= width as isize {
+                continue;
+            }
+            let q = qy as usize * width + qx as usize;
+
+            let color_q = &illumination[q];
+            let normal_q = Vector3::from(aovs.normal.pixels()[q].clone());
+            let albedo_q = Vector3::from(aovs.albedo.pixels()[q].clone());
+            let depth_q = aovs.depth.pixels()[q].r();
+
+            let w_color = gaussian((color_p - color_q).length_squared(), sigma_color);
+            let w_normal = gaussian(
+                (&normal_p - &normal_q).length_squared(),
+                settings.sigma_normal,
+            );
+            let w_albedo = gaussian(
+                (&albedo_p - &albedo_q).length_squared(),
+                settings.sigma_albedo,
+            );
+            let w_depth = depth_weight(depth_p, depth_q, settings.sigma_depth * step as f64);
+
+            let w = kx * ky * w_color * w_normal * w_albedo * w_depth;
+            sum += color_q * w;
+            weight_sum += w;
+        }
+    }
+
+    if weight_sum > 0.0 {
+        sum / weight_sum
+    } else {
+        color_p.clone()
+    }
+}
+
+fn gaussian(distance_squared: f64, sigma: f64) -> f64 {
+    f64::exp(-distance_squared / (sigma * sigma))
+}
+
+fn depth_weight(depth_p: f64, depth_q: f64, sigma: f64) -> f64 {
+    match (depth_p.is_finite(), depth_q.is_finite()) {
+        (true, true) => f64::exp(-(depth_p - depth_q).abs() / (sigma * depth_p.max(1e-6))),
+        (false, false) => 1.0,
+        _ => 0.0,
+    }
+}
+
+fn demodulate(color: &Vector3, albedo: &Vector3) -> Vector3 {
+    Vector3::new_from_iter(
+        color
+            .zip_elements(albedo)
+            .map(|(c, a)| if *a > ALBEDO_EPS { c / a } else { *c }),
+    )
+}
+
+fn remodulate(illumination: &Vector3, albedo: &Vector3) -> Vector3 {
+    Vector3::new_from_iter(illumination.zip_elements(albedo).map(|(l, a)| {
+        if *a > ALBEDO_EPS {
+            l * a
+        } else {
+            *l
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flat_aovs(width: usize, height: usize) -> Aovs {
+        let image = |c: Color| Image::from_pixels(width, height, vec![c; width * height]);
+        Aovs {
+            normal: image(Color::new(0.0, 0.0, 1.0)),
+            depth: image(Color::new_all(1.0)),
+            uv: image(Color::black()),
+            albedo: image(Color::new_all(0.5)),
+            object_id: image(Color::black()),
+        }
+    }
+
+    fn variance(image: &Image) -> f64 {
+        let n = image.pixels().len() as f64;
+        let mean = image.pixels().iter().map(|c| c.r()).sum::<f64>() / n;
+        image
+            .pixels()
+            .iter()
+            .map(|c| (c.r() - mean).powi(2))
+            .sum::<f64>()
+            / n
+    }
+
+    #[test]
+    fn smooths_noise_on_a_flat_surface() {
+        let (width, height) = (16, 16);
+        let noisy = Image::from_pixels(
+            width,
+            height,
+            (0..width * height)
+                .map(|i| Color::new_all(if i % 3 == 0 { 0.6 } else { 0.3 }))
+                .collect(),
+        );
+
+        let denoised = denoise(
+            &noisy,
+            &flat_aovs(width, height),
+            &DenoiseSettings::default(),
+        );
+
+        assert!(variance(&denoised) < variance(&noisy) / 10.0);
+    }
+
+    #[test]
+    fn keeps_edges_between_objects() {
+        let (width, height) = (16, 16);
+        let mut aovs = flat_aovs(width, height);
+        let mut beauty = Image::new(width, height);
+        for y in 0..height {
+            for x in width / 2..width {
+                aovs.depth.set_pixel(x, y, Color::new_all(10.0));
+                beauty.set_pixel(x, y, Color::new_all(0.5));
+            }
+        }
+
+        let denoised = denoise(&beauty, &aovs, &DenoiseSettings::default());
+
+        assert!(denoised.pixel(width / 2 - 1, 8).r() < 1e-9);
+        assert!((denoised.pixel(width / 2, 8).r() - 0.5).abs() < 1e-9);
+    }
+}
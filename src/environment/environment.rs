@@ -0,0 +1,8 @@
+use crate::vector3::{Color, Vector3};
+use std::fmt::Debug;
+
+/// Radiance arriving from infinitely far away, looked up by the direction of a ray that escaped
+/// the scene.
+pub trait Environment: Debug + Send + Sync {
+    fn value(&self, direction: &Vector3) -> Color;
+}
@@ -0,0 +1,32 @@
+use crate::environment::environment::Environment;
+use crate::vector3::{Color, Vector3};
+
+/// The white-to-blue gradient used as the background throughout the book.
+#[derive(Debug, Clone)]
+pub struct GradientSky {
+    horizon: Color,
+    zenith: Color,
+}
+
+impl GradientSky {
+    pub fn new(horizon: Color, zenith: Color) -> Self {
+        GradientSky { horizon, zenith }
+    }
+}
+
+impl Default for GradientSky {
+    fn default() -> Self {
+        Self::new(Color::white(), Color::new(0.5, 0.7, 1.0))
+    }
+}
+
+impl Environment for GradientSky {
+    fn value(&self, direction: &Vector3) -> Color {
+        let unit_direction = direction.unit_vector();
+        let t = 0.5 * (unit_direction.y() + 1.0);
+        Color::from(
+            Vector3::from(self.horizon.clone()) * (1.0 - t)
+                + Vector3::from(self.zenith.clone()) * t,
+        )
+    }
+}
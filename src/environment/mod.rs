@@ -0,0 +1,6 @@
+#[allow(clippy::module_inception)]
+pub mod environment;
+pub mod gradient_sky;
+pub mod physical_sky;
+pub mod sun;
+pub mod uniform;
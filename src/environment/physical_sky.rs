@@ -0,0 +1,234 @@
+use crate::degrees_to_radians;
+use crate::environment::environment::Environment;
+use crate::environment::sun::{direction_from_angles, Sun};
+use crate::vector3::{Color, Vector3};
+use std::f64::consts::PI;
+
+const SUN_ANGULAR_DIAMETER_DEGREES: f64 = 0.53;
+
+/// Analytic daylight following Preetham, Shirley and Smits, "A Practical Analytic Model for
+/// Daylight" (1999), together with the sun it is lit by.
+///
+/// Radiance comes out of the model in kcd/m² and is multiplied by `intensity` to land in the
+/// range the rest of the renderer expects.
+#[derive(Debug, Clone)]
+pub struct PhysicalSky {
+    sun: Sun,
+    turbidity: f64,
+    intensity: f64,
+    sun_intensity: f64,
+    sun_angular_diameter: f64,
+    ground_albedo: f64,
+    theta_sun: f64,
+    zenith: [f64; 3],
+    perez: [[f64; 5]; 3],
+}
+
+impl PhysicalSky {
+    /// `sun_elevation` and `sun_azimuth` are in radians, see [`Sun::from_angles`]. Useful
+    /// turbidities range from about 2 (very clear) to 10 (hazy).
+    pub fn new(sun_elevation: f64, sun_azimuth: f64, turbidity: f64) -> Self {
+        let sun_direction = direction_from_angles(sun_elevation, sun_azimuth);
+        let theta_sun = f64::acos(sun_direction.y().clamp(-1.0, 1.0));
+        let t = turbidity;
+
+        let perez = [
+            [
+                0.1787 * t - 1.4630,
+                -0.3554 * t + 0.4275,
+                -0.0227 * t + 5.3251,
+                0.1206 * t - 2.5771,
+                -0.0670 * t + 0.3703,
+            ],
+            [
+                -0.0193 * t - 0.2592,
+                -0.0665 * t + 0.0008,
+                -0.0004 * t + 0.2125,
+                -0.0641 * t - 0.8989,
+                -0.0033 * t + 0.0452,
+            ],
+            [
+                -0.0167 * t - 0.2608,
+                -0.0950 * t + 0.0092,
+                -0.0079 * t + 0.2102,
+                -0.0441 * t - 1.6537,
+                -0.0109 * t + 0.0529,
+            ],
+        ];
+
+        let theta = theta_sun.min(PI / 2.0);
+        let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * theta);
+        let zenith_luminance = ((4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192).max(0.0);
+        let zenith_x = zenith_chromaticity(
+            t,
+            theta,
+            [
+                [0.00166, -0.00375, 0.00209, 0.0],
+                [-0.02903, 0.06377, -0.03202, 0.00394],
+                [0.11693, -0.21196, 0.06052, 0.25886],
+            ],
+        );
+        let zenith_y = zenith_chromaticity(
+            t,
+            theta,
+            [
+                [0.00275, -0.00610, 0.00317, 0.0],
+                [-0.04214, 0.08970, -0.04153, 0.00516],
+                [0.15346, -0.26756, 0.06670, 0.26688],
+            ],
+        );
+
+        let mut sky = PhysicalSky {
+            sun: Sun::new(sun_direction, 0.0, Color::black()),
+            turbidity,
+            intensity: 0.1,
+            sun_intensity: 100.0,
+            sun_angular_diameter: degrees_to_radians(SUN_ANGULAR_DIAMETER_DEGREES),
+            ground_albedo: 0.3,
+            theta_sun,
+            zenith: [zenith_luminance, zenith_x, zenith_y],
+            perez,
+        };
+        sky.sun = sky.build_sun();
+        sky
+    }
+
+    pub fn with_intensity(mut self, intensity: f64) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    /// Scales the sun's radiance. Without light sampling the sun is only found by chance, so very
+    /// high values turn into fireflies rather than brighter direct light.
+    pub fn with_sun_intensity(mut self, sun_intensity: f64) -> Self {
+        self.sun_intensity = sun_intensity;
+        self.sun = self.build_sun();
+        self
+    }
+
+    /// `angular_diameter` is in radians; the real sun is about 0.53 degrees across.
+    pub fn with_sun_angular_diameter(mut self, angular_diameter: f64) -> Self {
+        self.sun_angular_diameter = angular_diameter;
+        self.sun = self.build_sun();
+        self
+    }
+
+    /// Directions below the horizon see the sky reflected by a diffuse ground of this albedo.
+    pub fn with_ground_albedo(mut self, ground_albedo: f64) -> Self {
+        self.ground_albedo = ground_albedo;
+        self
+    }
+
+    pub fn sun(&self) -> &Sun {
+        &self.sun
+    }
+    pub fn turbidity(&self) -> f64 {
+        self.turbidity
+    }
+
+    fn build_sun(&self) -> Sun {
+        let radiance = if self.theta_sun < PI / 2.0 {
+            let (_, x, y) = self.xyy(self.theta_sun, 0.0);
+            Color::from(xyy_to_rgb(x, y, 1.0) * self.sun_intensity)
+        } else {
+            Color::black()
+        };
+        Sun::new(
+            self.sun.direction().clone(),
+            self.sun_angular_diameter,
+            radiance,
+        )
+    }
+
+    fn xyy(&self, theta: f64, gamma: f64) -> (f64, f64, f64) {
+        let f = |i: usize| {
+            perez(&self.perez[i], theta, gamma) / perez(&self.perez[i], 0.0, self.theta_sun)
+        };
+        (
+            self.zenith[0] * f(0),
+            self.zenith[1] * f(1),
+            self.zenith[2] * f(2),
+        )
+    }
+
+    fn sky_value(&self, direction: &Vector3) -> Color {
+        let theta = f64::acos(direction.y().clamp(0.001, 1.0));
+        let cos_gamma = direction.dot(self.sun.direction()).clamp(-1.0, 1.0);
+        let (luminance, x, y) = self.xyy(theta, cos_gamma.acos());
+
+        Color::from(Vector3::new_from_iter(
+            xyy_to_rgb(x, y, luminance * self.intensity)
+                .iter_elements()
+                .map(|c| c.max(0.0)),
+        ))
+    }
+}
+
+impl Environment for PhysicalSky {
+    fn value(&self, direction: &Vector3) -> Color {
+        let unit_direction = direction.unit_vector();
+
+        if unit_direction.y() < 0.0 {
+            let mirrored =
+                Vector3::new(unit_direction.x(), -unit_direction.y(), unit_direction.z());
+            return Color::from(Vector3::from(self.sky_value(&mirrored)) * self.ground_albedo);
+        }
+
+        let sky = Vector3::from(self.sky_value(&unit_direction));
+        match self.sun.value(&unit_direction) {
+            Some(sun) => Color::from(sky + Vector3::from(sun)),
+            None => Color::from(sky),
+        }
+    }
+}
+
+fn perez(coefficients: &[f64; 5], theta: f64, gamma: f64) -> f64 {
+    let [a, b, c, d, e] = *coefficients;
+    (1.0 + a * f64::exp(b / theta.cos()))
+        * (1.0 + c * f64::exp(d * gamma) + e * gamma.cos().powi(2))
+}
+
+fn zenith_chromaticity(turbidity: f64, theta_sun: f64, matrix: [[f64; 4]; 3]) -> f64 {
+    let t = [turbidity.powi(2), turbidity, 1.0];
+    let theta = [theta_sun.powi(3), theta_sun.powi(2), theta_sun, 1.0];
+
+    (0..3)
+        .map(|i| t[i] * (0..4).map(|j| matrix[i][j] * theta[j]).sum::<f64>())
+        .sum()
+}
+
+fn xyy_to_rgb(x: f64, y: f64, luminance: f64) -> Vector3 {
+    let big_x = x / y * luminance;
+    let big_z = (1.0 - x - y) / y * luminance;
+
+    Vector3::new(
+        3.2406 * big_x - 1.5372 * luminance - 0.4986 * big_z,
+        -0.9689 * big_x + 1.8758 * luminance + 0.0415 * big_z,
+        0.0557 * big_x - 0.2040 * luminance + 1.0570 * big_z,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sky_is_blue_away_from_the_sun() {
+        let sky = PhysicalSky::new(degrees_to_radians(30.0), 0.0, 2.5);
+        let c = sky.value(&Vector3::new(-0.3, 1.0, 0.0));
+
+        assert!(c.b() > c.r());
+        assert!(c.r() > 0.0);
+    }
+
+    #[test]
+    fn sun_disk_is_brighter_than_sky() {
+        let sky = PhysicalSky::new(degrees_to_radians(30.0), 0.0, 2.5);
+        let toward_sun = sky.sun().direction().clone();
+        let beside_sun = Vector3::new(toward_sun.x(), toward_sun.y() + 0.1, toward_sun.z());
+
+        assert!(sky.value(&toward_sun).g() > sky.value(&beside_sun).g() * 10.0);
+        assert!(sky.sun().value(&toward_sun).is_some());
+        assert!(sky.sun().value(&beside_sun).is_none());
+    }
+}
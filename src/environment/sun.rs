@@ -0,0 +1,61 @@
+use crate::vector3::{Color, Vector3};
+
+/// A distant directional light seen as a small disk of constant radiance.
+#[derive(Debug, Clone)]
+pub struct Sun {
+    direction: Vector3,
+    angular_diameter: f64,
+    radiance: Color,
+    cos_half_angle: f64,
+}
+
+impl Sun {
+    /// `direction` points from the scene towards the sun; `angular_diameter` is in radians.
+    pub fn new(direction: Vector3, angular_diameter: f64, radiance: Color) -> Self {
+        Sun {
+            direction: direction.unit_vector(),
+            angular_diameter,
+            radiance,
+            cos_half_angle: f64::cos(angular_diameter / 2.0),
+        }
+    }
+
+    /// Places the sun by `elevation` above the horizon and `azimuth` from +x towards +z, both in
+    /// radians.
+    pub fn from_angles(
+        elevation: f64,
+        azimuth: f64,
+        angular_diameter: f64,
+        radiance: Color,
+    ) -> Self {
+        Self::new(
+            direction_from_angles(elevation, azimuth),
+            angular_diameter,
+            radiance,
+        )
+    }
+
+    pub fn direction(&self) -> &Vector3 {
+        &self.direction
+    }
+    pub fn angular_diameter(&self) -> f64 {
+        self.angular_diameter
+    }
+    pub fn radiance(&self) -> &Color {
+        &self.radiance
+    }
+
+    /// Radiance seen along `direction`, or `None` when it misses the sun's disk.
+    pub fn value(&self, direction: &Vector3) -> Option<Color> {
+        (direction.unit_vector().dot(&self.direction) >= self.cos_half_angle)
+            .then(|| self.radiance.clone())
+    }
+}
+
+pub(crate) fn direction_from_angles(elevation: f64, azimuth: f64) -> Vector3 {
+    Vector3::new(
+        elevation.cos() * azimuth.cos(),
+        elevation.sin(),
+        elevation.cos() * azimuth.sin(),
+    )
+}
@@ -0,0 +1,27 @@
+use crate::environment::environment::Environment;
+use crate::vector3::{Color, Vector3};
+
+/// The same radiance from every direction. The default is black, for scenes lit only by their
+/// own lights.
+#[derive(Debug, Clone)]
+pub struct Uniform {
+    color: Color,
+}
+
+impl Uniform {
+    pub fn new(color: Color) -> Self {
+        Uniform { color }
+    }
+}
+
+impl Default for Uniform {
+    fn default() -> Self {
+        Self::new(Color::black())
+    }
+}
+
+impl Environment for Uniform {
+    fn value(&self, _direction: &Vector3) -> Color {
+        self.color.clone()
+    }
+}
@@ -0,0 +1,40 @@
+use crate::image::Image;
+use crate::vector3::{Color, Vector3};
+
+const STOPS: [(f64, f64, f64); 5] = [
+    (0.0, 0.0, 0.5),
+    (0.0, 0.5, 1.0),
+    (0.2, 0.9, 0.2),
+    (1.0, 0.9, 0.0),
+    (0.9, 0.0, 0.0),
+];
+
+/// Maps `t` in `[0, 1]` onto a dark blue, cyan, green, yellow, red ramp for debug images.
+pub fn false_color(t: f64) -> Color {
+    let x = t.clamp(0.0, 1.0) * (STOPS.len() - 1) as f64;
+    let i = (x.floor() as usize).min(STOPS.len() - 2);
+    let f = x - i as f64;
+
+    let (r0, g0, b0) = STOPS[i];
+    let (r1, g1, b1) = STOPS[i + 1];
+    Color::from(Vector3::new(r0, g0, b0) * (1.0 - f) + Vector3::new(r1, g1, b1) * f)
+}
+
+/// A vertical bar of the ramp, 1 at the top and 0 at the bottom, with white ticks at every
+/// quarter so values can be read off against the maximum.
+pub fn legend(width: usize, height: usize) -> Image {
+    let mut image = Image::new(width, height);
+    let last = height.saturating_sub(1).max(1) as f64;
+    let ticks: Vec<_> = (0..=4)
+        .map(|q| (q as f64 / 4.0 * last).round() as usize)
+        .collect();
+
+    for y in 0..height {
+        let color = false_color(1.0 - y as f64 / last);
+        for x in 0..width {
+            let tick = ticks.contains(&y) && x < width.div_ceil(3);
+            image.set_pixel(x, y, if tick { Color::white() } else { color.clone() });
+        }
+    }
+    image
+}
@@ -0,0 +1,137 @@
+use crate::filter::Filter;
+use crate::vector3::{Color, Vector3};
+
+/// Weighted sums of filtered samples for a rectangle of an image.
+#[derive(Debug, Clone)]
+pub struct Film {
+    left: usize,
+    top: usize,
+    width: usize,
+    height: usize,
+    sums: Vec<Vector3>,
+    weights: Vec<f64>,
+}
+
+impl Film {
+    /// A film covering `width` by `height` pixels of an image, from column `left` and row `top`.
+    pub fn new(left: usize, top: usize, width: usize, height: usize) -> Self {
+        Film {
+            left,
+            top,
+            width,
+            height,
+            sums: vec![Vector3::zero(); width * height],
+            weights: vec![0.0; width * height],
+        }
+    }
+
+    pub fn left(&self) -> usize {
+        self.left
+    }
+    pub fn top(&self) -> usize {
+        self.top
+    }
+    pub fn width(&self) -> usize {
+        self.width
+    }
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Per pixel weighted sums of colors and sums of weights, row by row.
+    pub(crate) fn sums(&self) -> (&[Vector3], &[f64]) {
+        (&self.sums, &self.weights)
+    }
+
+    pub(crate) fn sums_mut(&mut self) -> (&mut [Vector3], &mut [f64]) {
+        (&mut self.sums, &mut self.weights)
+    }
+
+    /// Adds `color` seen at `(x, y)`, in pixels from the top left corner of the image, to every
+    /// pixel of the film whose center is within the filter's radius.
+    pub fn splat(&mut self, filter: &Filter, (x, y): (f64, f64), color: &Vector3) {
+        let radius = filter.radius();
+        let x0 = ((x - 0.5 - radius).ceil() as isize).max(self.left as isize);
+        let x1 = ((x - 0.5 + radius).floor() as isize).min((self.left + self.width) as isize - 1);
+        let y0 = ((y - 0.5 - radius).ceil() as isize).max(self.top as isize);
+        let y1 = ((y - 0.5 + radius).floor() as isize).min((self.top + self.height) as isize - 1);
+
+        for py in y0..=y1 {
+            for px in x0..=x1 {
+                let weight = filter.evaluate(px as f64 + 0.5 - x, py as f64 + 0.5 - y);
+                if weight == 0.0 {
+                    continue;
+                }
+                let i = (py as usize - self.top) * self.width + px as usize - self.left;
+                self.sums[i] += color * weight;
+                self.weights[i] += weight;
+            }
+        }
+    }
+
+    /// Adds the sums of `other`, wherever the two films overlap.
+    pub fn merge(&mut self, other: &Film) {
+        let left = self.left.max(other.left);
+        let right = (self.left + self.width).min(other.left + other.width);
+        let top = self.top.max(other.top);
+        let bottom = (self.top + self.height).min(other.top + other.height);
+
+        for y in top..bottom {
+            for x in left..right {
+                let a = (y - self.top) * self.width + x - self.left;
+                let b = (y - other.top) * other.width + x - other.left;
+                self.sums[a] += other.sums[b].clone();
+                self.weights[a] += other.weights[b];
+            }
+        }
+    }
+
+    /// The filtered colors, black where no sample landed.
+    pub fn colors(&self) -> Vec<Color> {
+        self.sums
+            .iter()
+            .zip(&self.weights)
+            .map(|(s, w)| {
+                if *w > 0.0 {
+                    Color::from(s / *w)
+                } else {
+                    Color::black()
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn box_filter_keeps_samples_in_their_pixel() {
+        let mut film = Film::new(0, 0, 3, 3);
+        let filter = Filter::default();
+        film.splat(&filter, (1.2, 1.9), &Vector3::one());
+        film.splat(&filter, (1.8, 1.1), &Vector3::zero());
+
+        let colors = film.colors();
+        assert_eq!(colors[4], Color::new_all(0.5));
+        assert_eq!(colors.iter().filter(|c| **c == Color::black()).count(), 8);
+    }
+
+    #[test]
+    fn wide_filters_reach_neighbours_across_merged_films() {
+        let filter = Filter::Tent { radius: 1.5 };
+        let mut top_left = Film::new(0, 0, 2, 2);
+        top_left.splat(&filter, (0.5, 0.5), &Vector3::one());
+        let mut bottom_right = Film::new(1, 1, 1, 1);
+        bottom_right.splat(&filter, (1.5, 1.5), &Vector3::zero());
+
+        let mut film = Film::new(0, 0, 2, 2);
+        film.merge(&top_left);
+        film.merge(&bottom_right);
+
+        let colors = film.colors();
+        assert_eq!(colors[0], Color::new_all(1.0));
+        assert!(colors[3].r() > 0.0 && colors[3].r() < 1.0);
+    }
+}
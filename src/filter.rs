@@ -0,0 +1,128 @@
+use std::f64::consts::PI;
+
+/// A pixel reconstruction filter: how much a sample at offset `(x, y)` pixels from a pixel
+/// center counts towards that pixel. Samples are splatted into every pixel within `radius`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Filter {
+    /// Equal weight inside the square. With a radius of half a pixel this is plain averaging.
+    Box { radius: f64 },
+    /// Weight falling off linearly to zero at `radius`.
+    Tent { radius: f64 },
+    /// A Gaussian of falloff `alpha`, shifted down so it reaches zero at `radius`.
+    Gaussian { radius: f64, alpha: f64 },
+    /// The Mitchell–Netravali cubic, stretched over `radius`. `b = c = 1/3` is the usual choice.
+    Mitchell { radius: f64, b: f64, c: f64 },
+    /// A sinc windowed by a wider sinc that reaches zero at `radius`.
+    Lanczos { radius: f64 },
+}
+
+impl Filter {
+    pub fn radius(&self) -> f64 {
+        match *self {
+            Self::Box { radius }
+            | Self::Tent { radius }
+            | Self::Gaussian { radius, .. }
+            | Self::Mitchell { radius, .. }
+            | Self::Lanczos { radius } => radius,
+        }
+    }
+
+    pub fn evaluate(&self, x: f64, y: f64) -> f64 {
+        let radius = self.radius();
+        if x.abs() > radius || y.abs() > radius {
+            return 0.0;
+        }
+
+        match *self {
+            Self::Box { .. } => 1.0,
+            Self::Tent { radius } => (radius - x.abs()) * (radius - y.abs()),
+            Self::Gaussian { radius, alpha } => {
+                let g = |d: f64| {
+                    (f64::exp(-alpha * d * d) - f64::exp(-alpha * radius * radius)).max(0.0)
+                };
+                g(x) * g(y)
+            }
+            Self::Mitchell { radius, b, c } => {
+                mitchell(2.0 * x / radius, b, c) * mitchell(2.0 * y / radius, b, c)
+            }
+            Self::Lanczos { radius } => {
+                let l = |d: f64| sinc(d) * sinc(d / radius);
+                l(x) * l(y)
+            }
+        }
+    }
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Self::Box { radius: 0.5 }
+    }
+}
+
+fn mitchell(x: f64, b: f64, c: f64) -> f64 {
+    let x = x.abs();
+    let v = if x < 1.0 {
+        (12.0 - 9.0 * b - 6.0 * c) * x.powi(3)
+            + (-18.0 + 12.0 * b + 6.0 * c) * x.powi(2)
+            + (6.0 - 2.0 * b)
+    } else if x < 2.0 {
+        (-b - 6.0 * c) * x.powi(3)
+            + (6.0 * b + 30.0 * c) * x.powi(2)
+            + (-12.0 * b - 48.0 * c) * x
+            + (8.0 * b + 24.0 * c)
+    } else {
+        0.0
+    };
+    v / 6.0
+}
+
+fn sinc(x: f64) -> f64 {
+    if x.abs() < 1e-5 {
+        1.0
+    } else {
+        f64::sin(PI * x) / (PI * x)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filters_peak_at_the_center_and_vanish_at_the_radius() {
+        let filters = [
+            Filter::Tent { radius: 1.0 },
+            Filter::Gaussian {
+                radius: 1.5,
+                alpha: 2.0,
+            },
+            Filter::Mitchell {
+                radius: 2.0,
+                b: 1.0 / 3.0,
+                c: 1.0 / 3.0,
+            },
+            Filter::Lanczos { radius: 3.0 },
+        ];
+
+        for filter in &filters {
+            let r = filter.radius();
+            assert!(
+                filter.evaluate(0.0, 0.0) > filter.evaluate(0.3, 0.2),
+                "{:?}",
+                filter
+            );
+            assert!(filter.evaluate(r, 0.0).abs() < 1e-9, "{:?}", filter);
+            assert_eq!(filter.evaluate(r + 0.1, 0.0), 0.0);
+        }
+    }
+
+    #[test]
+    fn mitchell_has_negative_lobes() {
+        let filter = Filter::Mitchell {
+            radius: 2.0,
+            b: 1.0 / 3.0,
+            c: 1.0 / 3.0,
+        };
+        assert!(filter.evaluate(1.5, 0.0) < 0.0);
+    }
+}
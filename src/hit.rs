@@ -2,7 +2,6 @@ use crate::bvh::aabb::AABB;
 use crate::material::material::Material;
 use crate::ray::Ray;
 use crate::vector3::{Point3, Vector3};
-use std::rc::Rc;
 use std::sync::Arc;
 
 #[derive(Debug, Clone)]
@@ -14,6 +13,7 @@ pub struct HitRecord {
     u: f64,
     v: f64,
     front_face: bool,
+    object_index: Option<usize>,
 }
 
 impl HitRecord {
@@ -38,6 +38,11 @@ impl HitRecord {
     pub fn material(&self) -> &Arc<dyn Material> {
         &self.material
     }
+    /// Position of the hit object in the list the world was built from, when the world is a
+    /// [`HitObjects`](crate::hit_objects::HitObjects) or a BVH over one.
+    pub fn object_index(&self) -> Option<usize> {
+        self.object_index
+    }
 }
 
 impl HitRecord {
@@ -64,14 +69,23 @@ impl HitRecord {
             v,
             front_face,
             material,
+            object_index: None,
         }
     }
+
+    pub(crate) fn with_object_index(mut self, object_index: usize) -> Self {
+        self.object_index = Some(object_index);
+        self
+    }
 }
 
 pub trait Hit: Sync + Send {
     fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord>;
     fn bounding_box(&self, time0: f64, time1: f64) -> Option<AABB>;
 
+    /// The squared distance from `point` to the closest point of the object, taken as solid so
+    /// that it is 0 from inside. Moving objects count everywhere they pass through.
     fn nearest_squared(&self, point: &Point3) -> f64;
+    /// The squared distance from `point` to the farthest point of the object.
     fn farest_squared(&self, point: &Point3) -> f64;
 }
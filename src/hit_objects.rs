@@ -4,6 +4,7 @@ use crate::hit::{Hit, HitRecord};
 use crate::moving_sphere::MovingSphere;
 use crate::ray::Ray;
 use crate::sphere::Sphere;
+use crate::telemetry::{self, Counter};
 use crate::vector3::Point3;
 use unwrap_ord::UnwrapOrd;
 
@@ -13,8 +14,19 @@ pub enum HitObject {
     MovingSphere(MovingSphere),
 }
 
+impl HitObject {
+    /// The point of the surface closest to `point` at `time`.
+    pub fn closest_point(&self, point: &Point3, time: f64) -> Point3 {
+        match self {
+            Self::Sphere(s) => s.closest_point(point),
+            Self::MovingSphere(s) => s.closest_point(point, time),
+        }
+    }
+}
+
 impl Hit for HitObject {
     fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        telemetry::count(Counter::PrimitiveTest);
         match self {
             Self::Sphere(s) => s.hit(ray, t_min, t_max),
             Self::MovingSphere(s) => s.hit(ray, t_min, t_max),
@@ -29,11 +41,17 @@ impl Hit for HitObject {
     }
 
     fn nearest_squared(&self, point: &Point3) -> f64 {
-        unimplemented!()
+        match self {
+            Self::Sphere(s) => s.nearest_squared(point),
+            Self::MovingSphere(s) => s.nearest_squared(point),
+        }
     }
 
     fn farest_squared(&self, point: &Point3) -> f64 {
-        unimplemented!()
+        match self {
+            Self::Sphere(s) => s.farest_squared(point),
+            Self::MovingSphere(s) => s.farest_squared(point),
+        }
     }
 }
 
@@ -55,6 +73,9 @@ impl HitObjects {
         self.0.push(object)
     }
 
+    /// Sorts the objects front to back as seen from `camera`, by the distance to their closest
+    /// points, so that nearby hits cut the search short sooner. Object indices in hit records
+    /// follow the new order.
     pub fn indexing_from_camera(&mut self, camera: &Camera) {
         let origin = camera.origin();
 
@@ -63,13 +84,21 @@ impl HitObjects {
     }
 }
 
+impl Default for HitObjects {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Hit for HitObjects {
     fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
         let mut record: Option<HitRecord> = None;
-        for obj in &self.0 {
+        for (i, obj) in self.0.iter().enumerate() {
             let t = record.as_ref().map(|x| x.t()).unwrap_or(t_max);
 
-            record = obj.hit(ray, t_min, t).or(record.clone());
+            if let Some(r) = obj.hit(ray, t_min, t) {
+                record = Some(r.with_object_index(i));
+            }
         }
         record
     }
@@ -92,11 +121,84 @@ impl Hit for HitObjects {
         result_box
     }
 
+    /// Infinite when there are no objects.
     fn nearest_squared(&self, point: &Point3) -> f64 {
-        unimplemented!()
+        self.0
+            .iter()
+            .map(|o| o.nearest_squared(point))
+            .fold(f64::INFINITY, f64::min)
     }
 
     fn farest_squared(&self, point: &Point3) -> f64 {
-        unimplemented!()
+        self.0
+            .iter()
+            .map(|o| o.farest_squared(point))
+            .fold(0.0, f64::max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::lambertian::Lambertian;
+    use crate::vector3::{Color, Vector3};
+    use std::sync::Arc;
+
+    fn sphere(z: f64) -> HitObject {
+        let material = Arc::new(Lambertian::new(Color::white()));
+        HitObject::Sphere(Sphere::new(Point3::new_z(z), 1.0, material))
+    }
+
+    #[test]
+    fn lists_take_the_nearest_and_farthest_object() {
+        let material = Arc::new(Lambertian::new(Color::white()));
+        let moving = HitObject::MovingSphere(MovingSphere::new(
+            Point3::new_x(3.0),
+            Point3::new_x(6.0),
+            1.0,
+            0.0,
+            1.0,
+            material,
+        ));
+        assert_eq!(moving.nearest_squared(&Point3::zero()), 4.0);
+        assert_eq!(sphere(-5.0).farest_squared(&Point3::zero()), 36.0);
+
+        let mut world = HitObjects::new();
+        assert_eq!(world.nearest_squared(&Point3::zero()), f64::INFINITY);
+        world.add(sphere(-5.0));
+        world.add(moving);
+        assert_eq!(world.nearest_squared(&Point3::zero()), 4.0);
+        assert_eq!(world.farest_squared(&Point3::zero()), 49.0);
+    }
+
+    #[test]
+    fn camera_indexing_sorts_front_to_back() {
+        let mut world = HitObjects::new();
+        for z in [-10.0, -2.5, -5.0] {
+            world.add(sphere(z));
+        }
+        let camera = Camera::new(
+            Point3::zero(),
+            Point3::new_z(-1.0),
+            Vector3::new_y(1.0),
+            90.0,
+            1.0,
+            0.0,
+            1.0,
+            0.0,
+            1.0,
+        );
+
+        world.indexing_from_camera(&camera);
+
+        let distances: Vec<_> = world
+            .0
+            .iter()
+            .map(|o| o.nearest_squared(&Point3::zero()))
+            .collect();
+        assert_eq!(distances, [2.25, 16.0, 81.0]);
+        let ray = Ray::new(Point3::zero(), Vector3::new_z(-1.0), 0.0);
+        let record = world.hit(&ray, 0.001, f64::INFINITY).unwrap();
+        assert_eq!(record.object_index(), Some(0));
     }
 }
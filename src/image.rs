@@ -0,0 +1,473 @@
+use crate::png;
+use crate::tone_mapping::ToneMapping;
+use crate::vector3::Color;
+use std::io::{self, BufRead, Read, Write};
+
+/// A grid of linear radiance values, stored row by row from the top of the picture.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+}
+
+impl Image {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self::from_pixels(width, height, vec![Color::black(); width * height])
+    }
+
+    pub fn from_pixels(width: usize, height: usize, pixels: Vec<Color>) -> Self {
+        assert_eq!(pixels.len(), width * height);
+        Image {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+    pub fn height(&self) -> usize {
+        self.height
+    }
+    pub fn pixels(&self) -> &[Color] {
+        &self.pixels
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> &Color {
+        &self.pixels[y * self.width + x]
+    }
+
+    pub fn set_pixel(&mut self, x: usize, y: usize, color: Color) {
+        self.pixels[y * self.width + x] = color;
+    }
+
+    /// Copies `image` over this one with its top left corner at column `x` and row `y`, dropping
+    /// whatever falls outside.
+    pub fn paste(&mut self, image: &Image, x: usize, y: usize) {
+        for row in 0..image.height.min(self.height.saturating_sub(y)) {
+            for column in 0..image.width.min(self.width.saturating_sub(x)) {
+                self.set_pixel(x + column, y + row, image.pixel(column, row).clone());
+            }
+        }
+    }
+
+    /// Writes a color Portable Float Map holding the linear values unchanged.
+    pub fn write_pfm<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        if self.width == 0 || self.height == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "a PFM needs at least one pixel",
+            ));
+        }
+        write!(out, "PF\n{} {}\n-1.0\n", self.width, self.height)?;
+
+        for row in self.pixels.chunks(self.width).rev() {
+            for pixel in row {
+                for c in [pixel.r(), pixel.g(), pixel.b()] {
+                    out.write_all(&(c as f32).to_le_bytes())?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads a Portable Float Map, color or grayscale, in either byte order.
+    pub fn read_pfm<R: BufRead>(input: &mut R) -> io::Result<Self> {
+        let channels = match read_token(input)?.as_str() {
+            "PF" => 3,
+            "Pf" => 1,
+            _ => return Err(invalid_data("not a PFM file")),
+        };
+        let width = parse_token::<usize, _>(input)?;
+        let height = parse_token::<usize, _>(input)?;
+        let little_endian = parse_token::<f64, _>(input)? < 0.0;
+        if width == 0 || height == 0 {
+            return Err(invalid_data("PFM image has no pixels"));
+        }
+        width
+            .checked_mul(height)
+            .and_then(|n| n.checked_mul(channels * 4))
+            .ok_or_else(|| invalid_data("PFM image is too large"))?;
+
+        // Grown as rows arrive, so a header alone cannot claim a huge allocation
+        let mut rows = Vec::new();
+        let mut bytes = [0; 4];
+        for _ in 0..height {
+            let mut row = Vec::new();
+            for _ in 0..width {
+                let mut c = [0.0; 3];
+                for v in c.iter_mut().take(channels) {
+                    input.read_exact(&mut bytes)?;
+                    *v = if little_endian {
+                        f32::from_le_bytes(bytes)
+                    } else {
+                        f32::from_be_bytes(bytes)
+                    } as f64;
+                }
+                if channels == 1 {
+                    c = [c[0]; 3];
+                }
+                row.push(Color::new(c[0], c[1], c[2]));
+            }
+            rows.push(row);
+        }
+
+        // PFM stores the bottom row first
+        let pixels = rows.into_iter().rev().flatten().collect();
+        Ok(Self::from_pixels(width, height, pixels))
+    }
+
+    /// Reads a binary or plain-text PPM. Unlike the float formats the values are display values,
+    /// scaled to `[0, 1]` but not linearized.
+    pub fn read_ppm<R: BufRead>(input: &mut R) -> io::Result<Self> {
+        let binary = match read_token(input)?.as_str() {
+            "P6" => true,
+            "P3" => false,
+            _ => return Err(invalid_data("not a PPM file")),
+        };
+        let width = parse_token::<usize, _>(input)?;
+        let height = parse_token::<usize, _>(input)?;
+        let max_value = parse_token::<u16, _>(input)?;
+        if max_value == 0 {
+            return Err(invalid_data("malformed header"));
+        }
+
+        let samples = width
+            .checked_mul(height)
+            .and_then(|n| n.checked_mul(3))
+            .ok_or_else(|| invalid_data("PPM image is too large"))?;
+
+        let mut values = Vec::new();
+        if binary {
+            let sample_size = if max_value > 255 { 2 } else { 1 };
+            let size = samples
+                .checked_mul(sample_size)
+                .ok_or_else(|| invalid_data("PPM image is too large"))?;
+            let mut bytes = Vec::new();
+            input.take(size as u64).read_to_end(&mut bytes)?;
+            if bytes.len() < size {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            values.extend(bytes.chunks(sample_size).map(|sample| match sample {
+                [high, low] => u16::from_be_bytes([*high, *low]),
+                [value] => *value as u16,
+                _ => unreachable!(),
+            }));
+        } else {
+            for _ in 0..samples {
+                values.push(parse_token::<u16, _>(input)?);
+            }
+        }
+
+        let max_value = max_value as f64;
+        let pixels = values
+            .chunks(3)
+            .map(|c| {
+                Color::new(
+                    c[0] as f64 / max_value,
+                    c[1] as f64 / max_value,
+                    c[2] as f64 / max_value,
+                )
+            })
+            .collect();
+        Ok(Self::from_pixels(width, height, pixels))
+    }
+
+    /// Reads a PNG, see [`png::decode`] for what is supported. As with [`Image::read_ppm`] the
+    /// values are display values.
+    pub fn read_png<R: Read>(input: &mut R) -> io::Result<Self> {
+        let mut data = Vec::new();
+        input.read_to_end(&mut data)?;
+        let (width, height, rgb) = png::decode(&data)?;
+        let pixels = rgb
+            .chunks(3)
+            .map(|c| {
+                Color::new(
+                    c[0] as f64 / 255.0,
+                    c[1] as f64 / 255.0,
+                    c[2] as f64 / 255.0,
+                )
+            })
+            .collect();
+        Ok(Self::from_pixels(width, height, pixels))
+    }
+
+    /// Reads a Radiance RGBE file with the usual `-Y height +X width` orientation, run-length
+    /// encoded or flat.
+    pub fn read_hdr<R: BufRead>(input: &mut R) -> io::Result<Self> {
+        let mut line = String::new();
+        input.read_line(&mut line)?;
+        if !line.starts_with("#?") {
+            return Err(invalid_data("not a Radiance HDR file"));
+        }
+        loop {
+            line.clear();
+            if input.read_line(&mut line)? == 0 {
+                return Err(invalid_data("malformed header"));
+            }
+            let line = line.trim();
+            if line.is_empty() {
+                break;
+            }
+            if let Some(format) = line.strip_prefix("FORMAT=") {
+                if format != "32-bit_rle_rgbe" {
+                    return Err(invalid_data("unsupported HDR pixel format"));
+                }
+            }
+        }
+
+        line.clear();
+        input.read_line(&mut line)?;
+        let (height, width): (usize, usize) = match line.split_whitespace().collect::<Vec<_>>()[..]
+        {
+            ["-Y", height, "+X", width] => (
+                height
+                    .parse()
+                    .map_err(|_| invalid_data("malformed header"))?,
+                width
+                    .parse()
+                    .map_err(|_| invalid_data("malformed header"))?,
+            ),
+            _ => return Err(invalid_data("unsupported HDR orientation")),
+        };
+        if width == 0 || height == 0 {
+            return Err(invalid_data("HDR image has no pixels"));
+        }
+        let row_size = width
+            .checked_mul(4)
+            .filter(|_| width.checked_mul(height).is_some())
+            .ok_or_else(|| invalid_data("HDR image is too large"))?;
+
+        // Grown as scanlines arrive, so a header alone cannot claim a huge allocation
+        let mut pixels = Vec::new();
+        let mut scanline = vec![0u8; row_size];
+        for _ in 0..height {
+            read_hdr_scanline(input, &mut scanline)?;
+            pixels.extend(scanline.chunks(4).map(|rgbe| {
+                if rgbe[3] == 0 {
+                    return Color::black();
+                }
+                // The mantissas are the top 8 bits, so the middle of their range is at + 0.5
+                let scale = 2f64.powi(rgbe[3] as i32 - 136);
+                Color::new(
+                    (rgbe[0] as f64 + 0.5) * scale,
+                    (rgbe[1] as f64 + 0.5) * scale,
+                    (rgbe[2] as f64 + 0.5) * scale,
+                )
+            }));
+        }
+        Ok(Self::from_pixels(width, height, pixels))
+    }
+
+    /// Writes an 8-bit RGB PNG, tone mapping each pixel.
+    pub fn write_png<W: Write>(&self, out: &mut W, tone_mapping: &ToneMapping) -> io::Result<()> {
+        out.write_all(&png::encode(
+            self.width,
+            self.height,
+            &self.to_rgb8(tone_mapping),
+        ))
+    }
+
+    /// The tone mapped pixels, three bytes each.
+    pub fn to_rgb8(&self, tone_mapping: &ToneMapping) -> Vec<u8> {
+        self.pixels
+            .iter()
+            .flat_map(|pixel| tone_mapping.to_rgb8(pixel))
+            .collect()
+    }
+
+    /// Writes a plain-text PPM, tone mapping each pixel down to 8 bits.
+    pub fn write_ppm<W: Write>(&self, out: &mut W, tone_mapping: &ToneMapping) -> io::Result<()> {
+        writeln!(out, "P3")?;
+        writeln!(out, "{} {}", self.width, self.height)?;
+        writeln!(out, "255")?;
+
+        for pixel in &self.pixels {
+            let [r, g, b] = tone_mapping.to_rgb8(pixel);
+            writeln!(out, "{} {} {}", r, g, b)?;
+        }
+        Ok(())
+    }
+}
+
+/// Fills `scanline` with RGBE quadruples. Run-length encoded scanlines store each of the four
+/// components in turn, as runs of one repeated byte or of literal bytes.
+fn read_hdr_scanline<R: BufRead>(input: &mut R, scanline: &mut [u8]) -> io::Result<()> {
+    let width = scanline.len() / 4;
+    let mut start = [0u8; 4];
+    input.read_exact(&mut start)?;
+    let encoded = (8..0x8000).contains(&width) && start[0] == 2 && start[1] == 2;
+    if !encoded || start[2] & 0x80 != 0 {
+        scanline[..4].copy_from_slice(&start);
+        return input.read_exact(&mut scanline[4..]);
+    }
+    if u16::from_be_bytes([start[2], start[3]]) as usize != width {
+        return Err(invalid_data("HDR scanline width mismatch"));
+    }
+
+    let mut byte = [0u8];
+    for component in 0..4 {
+        let mut x = 0;
+        while x < width {
+            input.read_exact(&mut byte)?;
+            let (run, count) = if byte[0] > 128 {
+                (true, byte[0] as usize - 128)
+            } else {
+                (false, byte[0] as usize)
+            };
+            if count == 0 || x + count > width {
+                return Err(invalid_data("bad HDR run length"));
+            }
+            if run {
+                input.read_exact(&mut byte)?;
+            }
+            for _ in 0..count {
+                if !run {
+                    input.read_exact(&mut byte)?;
+                }
+                scanline[4 * x + component] = byte[0];
+                x += 1;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The next whitespace separated word of a Netpbm style header, skipping `#` comments. Consumes
+/// the single whitespace byte that ends it, after which binary data may start.
+pub(crate) fn read_token<R: BufRead>(input: &mut R) -> io::Result<String> {
+    let mut token = Vec::new();
+    let mut byte = [0];
+    loop {
+        input.read_exact(&mut byte)?;
+        match byte[0] {
+            b'#' if token.is_empty() => {
+                let mut comment = Vec::new();
+                input.read_until(b'\n', &mut comment)?;
+            }
+            b if b.is_ascii_whitespace() => {
+                if !token.is_empty() {
+                    break;
+                }
+            }
+            b => token.push(b),
+        }
+    }
+    String::from_utf8(token).map_err(|_| invalid_data("malformed header"))
+}
+
+pub(crate) fn parse_token<T: std::str::FromStr, R: BufRead>(input: &mut R) -> io::Result<T> {
+    read_token(input)?
+        .parse()
+        .map_err(|_| invalid_data("malformed header"))
+}
+
+pub(crate) fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tone_mapping::{ToneMapOperator, TransferFunction};
+
+    #[test]
+    fn pfm_round_trips() {
+        let mut image = Image::new(3, 2);
+        image.set_pixel(0, 0, Color::new(0.25, 2.0, 0.0));
+        image.set_pixel(2, 1, Color::new(1.0, 0.5, 8.0));
+
+        let mut pfm = Vec::new();
+        image.write_pfm(&mut pfm).unwrap();
+
+        assert_eq!(Image::read_pfm(&mut &pfm[..]).unwrap(), image);
+        assert!(Image::read_pfm(&mut &b"P3\n1 1\n255\n0 0 0\n"[..]).is_err());
+    }
+
+    #[test]
+    fn ppm_and_png_read_back_as_display_values() {
+        let mut image = Image::new(2, 2);
+        image.set_pixel(0, 0, Color::new(0.2, 0.4, 1.0));
+        image.set_pixel(1, 1, Color::new(1.0, 0.0, 0.6));
+        let linear = ToneMapping::new(ToneMapOperator::Clamp, TransferFunction::Linear);
+
+        let mut ppm = Vec::new();
+        image.write_ppm(&mut ppm, &linear).unwrap();
+        let mut png = Vec::new();
+        image.write_png(&mut png, &linear).unwrap();
+        for read in [
+            Image::read_ppm(&mut &ppm[..]).unwrap(),
+            Image::read_png(&mut &png[..]).unwrap(),
+        ] {
+            assert_eq!(read.to_rgb8(&linear), image.to_rgb8(&linear));
+        }
+
+        let binary = b"P6\n# 16 bits\n1 1\n65535\n\xff\xff\x80\x00\x00\x00";
+        let read = Image::read_ppm(&mut &binary[..]).unwrap();
+        assert_eq!(read.pixel(0, 0).r(), 1.0);
+        assert!((read.pixel(0, 0).g() - 0.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn hdr_reads_run_length_encoded_and_flat_scanlines() {
+        let mut hdr = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 2 +X 8\n".to_vec();
+        // Red as one run, green as literals, blue as two runs, then the exponent
+        hdr.extend_from_slice(&[2, 2, 0, 8, 136, 127]);
+        hdr.extend_from_slice(&[8, 0, 1, 2, 3, 4, 5, 6, 7]);
+        hdr.extend_from_slice(&[132, 0, 132, 255, 136, 129]);
+        for x in 0..8 {
+            hdr.extend_from_slice(&[63, 63, 63, if x == 0 { 0 } else { 128 }]);
+        }
+
+        let image = Image::read_hdr(&mut &hdr[..]).unwrap();
+        assert_eq!((image.width(), image.height()), (8, 2));
+        assert_eq!(
+            image.pixel(0, 0),
+            &Color::new(127.5 / 128.0, 0.5 / 128.0, 0.5 / 128.0)
+        );
+        assert_eq!(
+            image.pixel(7, 0),
+            &Color::new(127.5 / 128.0, 7.5 / 128.0, 255.5 / 128.0)
+        );
+        assert_eq!(image.pixel(0, 1), &Color::black());
+        assert_eq!(image.pixel(3, 1), &Color::new_all(63.5 / 256.0));
+    }
+
+    #[test]
+    fn empty_and_oversized_headers_are_invalid_data() {
+        for header in [
+            "-Y 0 +X 8",
+            "-Y 2 +X 0",
+            "-Y 0 +X 0",
+            "-Y 18446744073709551615 +X 2",
+        ] {
+            let hdr = format!("#?RADIANCE\n\n{}\n", header);
+            let error = Image::read_hdr(&mut hdr.as_bytes()).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData, "{}", header);
+        }
+
+        let ppm = b"P6 18446744073709551615 2 255\n";
+        let error = Image::read_ppm(&mut &ppm[..]).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        for header in [
+            "PF 0 2 -1.0",
+            "Pf 3 0 -1.0",
+            "PF 4611686018427387904 4 -1.0",
+        ] {
+            let pfm = format!("{}\n", header);
+            let error = Image::read_pfm(&mut pfm.as_bytes()).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData, "{}", header);
+        }
+        // Claims a huge image but holds one pixel, which runs out before anything is reserved
+        let mut pfm = b"PF 100000000 100000000 -1.0\n".to_vec();
+        pfm.extend_from_slice(&[0; 12]);
+        let error = Image::read_pfm(&mut &pfm[..]).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+
+        let error = Image::new(0, 3).write_pfm(&mut Vec::new()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    }
+}
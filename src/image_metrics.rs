@@ -0,0 +1,462 @@
+//! Measures of how far an image is from a reference. Values are clamped to `[0, 1]` first, so
+//! images should be compared in a common display or linear range.
+
+use crate::false_color::false_color;
+use crate::image::Image;
+use crate::tone_mapping::luminance;
+use crate::vector3::{Color, Vector3};
+use std::f64::consts::PI;
+
+const SSIM_WINDOW: usize = 7;
+const SSIM_C1: f64 = 0.01 * 0.01;
+const SSIM_C2: f64 = 0.03 * 0.03;
+
+// FLIP's default viewing conditions, a 0.7 m wide 4K screen seen from 0.7 m away
+const PIXELS_PER_DEGREE: f64 = 67.0;
+/// The spread of the contrast sensitivity of the achromatic, red-green and blue-yellow channels.
+const FLIP_CHANNEL_SPREADS: [f64; 3] = [0.0047, 0.0053, 0.04];
+/// The width in degrees of the edges and points that features are detected at.
+const FLIP_FEATURE_WIDTH: f64 = 0.082;
+const FLIP_COLOR_EXPONENT: f64 = 0.7;
+const FLIP_FEATURE_EXPONENT: f64 = 0.5;
+const FLIP_COLOR_KNEE: f64 = 0.4;
+const FLIP_COLOR_KNEE_ERROR: f64 = 0.95;
+
+const RGB_TO_XYZ: [[f64; 3]; 3] = [
+    [0.412_456_4, 0.357_576_1, 0.180_437_5],
+    [0.212_672_9, 0.715_152_2, 0.072_175_0],
+    [0.019_333_9, 0.119_192_0, 0.950_304_1],
+];
+const XYZ_TO_RGB: [[f64; 3]; 3] = [
+    [3.240_454_2, -1.537_138_5, -0.498_531_4],
+    [-0.969_266_0, 1.876_010_8, 0.041_556_0],
+    [0.055_643_4, -0.204_025_9, 1.057_225_2],
+];
+const WHITE: [f64; 3] = [0.950_470, 1.0, 1.088_830];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Comparison {
+    pub mean_absolute_error: f64,
+    pub rmse: f64,
+    /// In decibels, infinite for identical images.
+    pub psnr: f64,
+    /// 1 for identical images.
+    pub ssim: f64,
+    /// The mean of [`flip_errors`], 0 for identical images.
+    pub flip: f64,
+}
+
+pub fn compare(reference: &Image, test: &Image) -> Comparison {
+    let rmse = rmse(reference, test);
+    Comparison {
+        mean_absolute_error: mean_absolute_error(reference, test),
+        rmse,
+        psnr: psnr_from_rmse(rmse),
+        ssim: ssim(reference, test),
+        flip: flip(reference, test),
+    }
+}
+
+fn channels(image: &Image) -> impl Iterator<Item = f64> + '_ {
+    image
+        .pixels()
+        .iter()
+        .flat_map(|c| [c.r(), c.g(), c.b()])
+        .map(|v| v.clamp(0.0, 1.0))
+}
+
+fn check_sizes(reference: &Image, test: &Image) {
+    assert_eq!(
+        (reference.width(), reference.height()),
+        (test.width(), test.height()),
+        "images differ in size"
+    );
+}
+
+/// Averaged over all channels of all pixels.
+pub fn mean_absolute_error(reference: &Image, test: &Image) -> f64 {
+    check_sizes(reference, test);
+    let n = reference.pixels().len() * 3;
+    let sum: f64 = channels(reference)
+        .zip(channels(test))
+        .map(|(a, b)| (a - b).abs())
+        .sum();
+    sum / n.max(1) as f64
+}
+
+pub fn rmse(reference: &Image, test: &Image) -> f64 {
+    check_sizes(reference, test);
+    let n = reference.pixels().len() * 3;
+    let sum: f64 = channels(reference)
+        .zip(channels(test))
+        .map(|(a, b)| (a - b) * (a - b))
+        .sum();
+    f64::sqrt(sum / n.max(1) as f64)
+}
+
+pub fn psnr(reference: &Image, test: &Image) -> f64 {
+    psnr_from_rmse(rmse(reference, test))
+}
+
+fn psnr_from_rmse(rmse: f64) -> f64 {
+    if rmse == 0.0 {
+        f64::INFINITY
+    } else {
+        -20.0 * rmse.log10()
+    }
+}
+
+fn luminances(image: &Image) -> Vec<f64> {
+    image
+        .pixels()
+        .iter()
+        .map(|c| luminance(&Vector3::from(c.clone())).clamp(0.0, 1.0))
+        .collect()
+}
+
+/// Structural similarity of the luminance, averaged over every 7 by 7 window, or over the whole
+/// image if it is smaller than that.
+pub fn ssim(reference: &Image, test: &Image) -> f64 {
+    check_sizes(reference, test);
+    let (width, height) = (reference.width(), reference.height());
+    let a = luminances(reference);
+    let b = luminances(test);
+
+    let window_width = SSIM_WINDOW.min(width);
+    let window_height = SSIM_WINDOW.min(height);
+    let mut total = 0.0;
+    let mut windows = 0;
+
+    for y0 in 0..=height - window_height {
+        for x0 in 0..=width - window_width {
+            let indices = (y0..y0 + window_height)
+                .flat_map(|y| (x0..x0 + window_width).map(move |x| y * width + x));
+            total += window_ssim(indices.map(|i| (a[i], b[i])));
+            windows += 1;
+        }
+    }
+    total / windows.max(1) as f64
+}
+
+fn window_ssim(values: impl Iterator<Item = (f64, f64)>) -> f64 {
+    let values: Vec<_> = values.collect();
+    let n = values.len() as f64;
+    let mean_a = values.iter().map(|v| v.0).sum::<f64>() / n;
+    let mean_b = values.iter().map(|v| v.1).sum::<f64>() / n;
+
+    let (mut var_a, mut var_b, mut covariance) = (0.0, 0.0, 0.0);
+    for (a, b) in &values {
+        var_a += (a - mean_a) * (a - mean_a);
+        var_b += (b - mean_b) * (b - mean_b);
+        covariance += (a - mean_a) * (b - mean_b);
+    }
+    let denominator = (n - 1.0).max(1.0);
+    let (var_a, var_b, covariance) = (
+        var_a / denominator,
+        var_b / denominator,
+        covariance / denominator,
+    );
+
+    ((2.0 * mean_a * mean_b + SSIM_C1) * (2.0 * covariance + SSIM_C2))
+        / ((mean_a * mean_a + mean_b * mean_b + SSIM_C1) * (var_a + var_b + SSIM_C2))
+}
+
+/// The mean of [`flip_errors`].
+pub fn flip(reference: &Image, test: &Image) -> f64 {
+    let errors = flip_errors(reference, test);
+    errors.iter().sum::<f64>() / errors.len().max(1) as f64
+}
+
+/// A simplified take on NVIDIA's FLIP, for images of sRGB display values: the colors are blurred
+/// as the eye blurs them on a typical screen, compared in a perceptually uniform space, and the
+/// difference is raised where edges or points differ. One error per pixel, row by row, from 0 for
+/// no visible difference to 1.
+pub fn flip_errors(reference: &Image, test: &Image) -> Vec<f64> {
+    check_sizes(reference, test);
+    let (width, height) = (reference.width(), reference.height());
+
+    let max_color_error =
+        hyab(&lab(&[0.0, 1.0, 0.0]), &lab(&[0.0, 0.0, 1.0])).powf(FLIP_COLOR_EXPONENT);
+    let colors_a = filtered_lab(reference);
+    let colors_b = filtered_lab(test);
+    let features_a = features(reference);
+    let features_b = features(test);
+
+    (0..width * height)
+        .map(|i| {
+            let color_error = hyab(&colors_a[i], &colors_b[i]).powf(FLIP_COLOR_EXPONENT);
+            // Small differences count for most of the range, large ones saturate
+            let knee = FLIP_COLOR_KNEE * max_color_error;
+            let color_error = if color_error < knee {
+                color_error * FLIP_COLOR_KNEE_ERROR / knee
+            } else {
+                FLIP_COLOR_KNEE_ERROR
+                    + (color_error - knee) / (max_color_error - knee)
+                        * (1.0 - FLIP_COLOR_KNEE_ERROR)
+            }
+            .min(1.0);
+
+            let (edge_a, point_a) = features_a[i];
+            let (edge_b, point_b) = features_b[i];
+            let feature_error = ((edge_a - edge_b).abs().max((point_a - point_b).abs())
+                / f64::sqrt(2.0))
+            .min(1.0)
+            .powf(FLIP_FEATURE_EXPONENT);
+
+            color_error.powf(1.0 - feature_error)
+        })
+        .collect()
+}
+
+/// The per-pixel [`flip_errors`] in false color.
+pub fn flip_image(reference: &Image, test: &Image) -> Image {
+    let pixels = flip_errors(reference, test)
+        .into_iter()
+        .map(false_color)
+        .collect();
+    Image::from_pixels(reference.width(), reference.height(), pixels)
+}
+
+fn linear_rgb(c: &Color) -> [f64; 3] {
+    [c.r(), c.g(), c.b()].map(|v| {
+        let v = v.clamp(0.0, 1.0);
+        if v <= 0.04045 {
+            v / 12.92
+        } else {
+            ((v + 0.055) / 1.055).powf(2.4)
+        }
+    })
+}
+
+fn multiply(m: &[[f64; 3]; 3], v: &[f64; 3]) -> [f64; 3] {
+    m.map(|row| row[0] * v[0] + row[1] * v[1] + row[2] * v[2])
+}
+
+/// CIELAB without its cube root, so that it can be blurred linearly.
+fn ycxcz(rgb: &[f64; 3]) -> [f64; 3] {
+    let xyz = multiply(&RGB_TO_XYZ, rgb);
+    let [x, y, z] = [xyz[0] / WHITE[0], xyz[1] / WHITE[1], xyz[2] / WHITE[2]];
+    [116.0 * y - 16.0, 500.0 * (x - y), 200.0 * (y - z)]
+}
+
+fn ycxcz_to_rgb(ycxcz: &[f64; 3]) -> [f64; 3] {
+    let y = (ycxcz[0] + 16.0) / 116.0;
+    let x = ycxcz[1] / 500.0 + y;
+    let z = y - ycxcz[2] / 200.0;
+    multiply(&XYZ_TO_RGB, &[x * WHITE[0], y * WHITE[1], z * WHITE[2]]).map(|v| v.clamp(0.0, 1.0))
+}
+
+/// CIELAB with FLIP's Hunt adjustment, which fades the chroma of dark colors.
+fn lab(rgb: &[f64; 3]) -> [f64; 3] {
+    let xyz = multiply(&RGB_TO_XYZ, rgb);
+    let f = |t: f64| {
+        let delta = 6.0 / 29.0;
+        if t > delta * delta * delta {
+            t.cbrt()
+        } else {
+            t / (3.0 * delta * delta) + 4.0 / 29.0
+        }
+    };
+    let [x, y, z] = [
+        f(xyz[0] / WHITE[0]),
+        f(xyz[1] / WHITE[1]),
+        f(xyz[2] / WHITE[2]),
+    ];
+    let l = 116.0 * y - 16.0;
+    [l, 0.01 * l * 500.0 * (x - y), 0.01 * l * 200.0 * (y - z)]
+}
+
+/// The distance in lightness plus the distance in chroma.
+fn hyab(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    (a[0] - b[0]).abs() + f64::hypot(a[1] - b[1], a[2] - b[2])
+}
+
+fn filtered_lab(image: &Image) -> Vec<[f64; 3]> {
+    let (width, height) = (image.width(), image.height());
+    let opponent: Vec<[f64; 3]> = image
+        .pixels()
+        .iter()
+        .map(|c| ycxcz(&linear_rgb(c)))
+        .collect();
+
+    let channels = [0, 1, 2].map(|channel| {
+        let values: Vec<f64> = opponent.iter().map(|c| c[channel]).collect();
+        let spread = FLIP_CHANNEL_SPREADS[channel];
+        let sigma = f64::sqrt(spread / (2.0 * PI * PI)) * PIXELS_PER_DEGREE;
+        let kernel = gaussian(sigma);
+        convolve(&values, width, height, &kernel, &kernel)
+    });
+    (0..width * height)
+        .map(|i| {
+            let c = [channels[0][i], channels[1][i], channels[2][i]];
+            lab(&ycxcz_to_rgb(&c))
+        })
+        .collect()
+}
+
+/// The strength of edges and points in the lightness of each pixel.
+fn features(image: &Image) -> Vec<(f64, f64)> {
+    let (width, height) = (image.width(), image.height());
+    let lightness: Vec<f64> = image
+        .pixels()
+        .iter()
+        .map(|c| (ycxcz(&linear_rgb(c))[0] + 16.0) / 116.0)
+        .collect();
+
+    let sigma = 0.5 * FLIP_FEATURE_WIDTH * PIXELS_PER_DEGREE;
+    let smooth = gaussian(sigma);
+    let radius = (smooth.len() / 2) as f64;
+    let first = balanced((0..smooth.len()).map(|i| {
+        let x = i as f64 - radius;
+        -x * smooth[i]
+    }));
+    let second = balanced((0..smooth.len()).map(|i| {
+        let x = i as f64 - radius;
+        (x * x / (sigma * sigma) - 1.0) * smooth[i]
+    }));
+
+    let edge_x = convolve(&lightness, width, height, &first, &smooth);
+    let edge_y = convolve(&lightness, width, height, &smooth, &first);
+    let point_x = convolve(&lightness, width, height, &second, &smooth);
+    let point_y = convolve(&lightness, width, height, &smooth, &second);
+    (0..width * height)
+        .map(|i| {
+            (
+                f64::hypot(edge_x[i], edge_y[i]),
+                f64::hypot(point_x[i], point_y[i]),
+            )
+        })
+        .collect()
+}
+
+/// A normalized Gaussian reaching out three standard deviations.
+fn gaussian(sigma: f64) -> Vec<f64> {
+    let radius = (3.0 * sigma).ceil() as i64;
+    let kernel: Vec<f64> = (-radius..=radius)
+        .map(|x| f64::exp(-((x * x) as f64) / (2.0 * sigma * sigma)))
+        .collect();
+    let sum: f64 = kernel.iter().sum();
+    kernel.into_iter().map(|k| k / sum).collect()
+}
+
+/// Scales the positive weights to sum to 1 and the negative ones to -1.
+fn balanced(kernel: impl Iterator<Item = f64>) -> Vec<f64> {
+    let kernel: Vec<f64> = kernel.collect();
+    let positive: f64 = kernel.iter().filter(|k| **k > 0.0).sum();
+    let negative: f64 = -kernel.iter().filter(|k| **k < 0.0).sum::<f64>();
+    kernel
+        .into_iter()
+        .map(|k| if k > 0.0 { k / positive } else { k / negative })
+        .collect()
+}
+
+/// Filters the rows with `horizontal` and then the columns with `vertical`, repeating the edge
+/// pixels outwards.
+fn convolve(
+    values: &[f64],
+    width: usize,
+    height: usize,
+    horizontal: &[f64],
+    vertical: &[f64],
+) -> Vec<f64> {
+    let clamp = |i: i64, size: usize| i.clamp(0, size as i64 - 1) as usize;
+
+    let radius = (horizontal.len() / 2) as i64;
+    let mut rows = vec![0.0; values.len()];
+    for y in 0..height {
+        for x in 0..width {
+            rows[y * width + x] = horizontal
+                .iter()
+                .enumerate()
+                .map(|(k, weight)| {
+                    weight * values[y * width + clamp(x as i64 + k as i64 - radius, width)]
+                })
+                .sum();
+        }
+    }
+
+    let radius = (vertical.len() / 2) as i64;
+    let mut out = vec![0.0; values.len()];
+    for y in 0..height {
+        for x in 0..width {
+            out[y * width + x] = vertical
+                .iter()
+                .enumerate()
+                .map(|(k, weight)| {
+                    weight * rows[clamp(y as i64 + k as i64 - radius, height) * width + x]
+                })
+                .sum();
+        }
+    }
+    out
+}
+
+/// Colors each pixel by the largest difference of its channels, from blue for none to red for
+/// `scale` or more.
+pub fn difference_image(reference: &Image, test: &Image, scale: f64) -> Image {
+    check_sizes(reference, test);
+    let pixels = reference
+        .pixels()
+        .iter()
+        .zip(test.pixels())
+        .map(|(a, b)| {
+            let d = (a.r() - b.r())
+                .abs()
+                .max((a.g() - b.g()).abs())
+                .max((a.b() - b.b()).abs());
+            false_color(d / scale)
+        })
+        .collect();
+    Image::from_pixels(reference.width(), reference.height(), pixels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gradient(offset: f64) -> Image {
+        let pixels = (0..100)
+            .map(|i| Color::new_all(((i % 10) as f64 / 10.0 + offset).min(1.0)))
+            .collect();
+        Image::from_pixels(10, 10, pixels)
+    }
+
+    #[test]
+    fn identical_images_compare_perfectly() {
+        let comparison = compare(&gradient(0.0), &gradient(0.0));
+        assert_eq!(comparison.rmse, 0.0);
+        assert_eq!(comparison.psnr, f64::INFINITY);
+        assert!((comparison.ssim - 1.0).abs() < 1e-12);
+        assert_eq!(comparison.flip, 0.0);
+    }
+
+    #[test]
+    fn offsets_lower_psnr_and_noise_lowers_ssim() {
+        let reference = gradient(0.0);
+        let brighter = gradient(0.01);
+        assert!((psnr(&reference, &brighter) - 40.0).abs() < 1.0);
+
+        let mut noisy = reference.clone();
+        for i in (0..10).step_by(2) {
+            for j in 0..10 {
+                noisy.set_pixel(i, j, Color::new_all(1.0));
+            }
+        }
+        assert!(ssim(&reference, &noisy) < ssim(&reference, &brighter));
+        assert!(ssim(&reference, &brighter) > 0.95);
+        assert!(flip(&reference, &brighter) < flip(&reference, &noisy));
+    }
+
+    #[test]
+    fn flip_saturates_for_opposite_colors() {
+        let black = Image::new(8, 8);
+        let white = Image::from_pixels(8, 8, vec![Color::new_all(1.0); 64]);
+        let grey = Image::from_pixels(8, 8, vec![Color::new_all(0.5); 64]);
+
+        assert!(flip(&black, &white) > 0.95);
+        assert!(flip(&black, &grey) < flip(&black, &white));
+        assert!(flip_errors(&black, &white)
+            .iter()
+            .all(|e| (0.0..=1.0).contains(e)));
+    }
+}
@@ -1,14 +1,40 @@
 use std::f64::consts::PI;
 
+pub mod adaptive;
+pub mod aov;
 pub mod bvh;
 pub mod camera;
+pub mod denoise;
+pub mod environment;
+pub mod false_color;
+pub mod film;
+pub mod filter;
 pub mod hit;
 pub mod hit_objects;
+pub mod image;
+pub mod image_metrics;
 pub mod material;
 pub mod moving_sphere;
+pub mod path_inspector;
+pub mod picking;
+pub mod png;
+#[cfg(feature = "preview-server")]
+pub mod preview_server;
+pub mod progressive;
 pub mod ray;
+pub mod ray_query;
+pub mod render;
+pub mod sampler;
+pub mod scenes;
+pub mod scheduler;
 pub mod sphere;
+pub mod stereo;
+pub mod telemetry;
+pub mod terminal_preview;
 pub mod texture;
+pub mod tone_mapping;
+#[cfg(feature = "telemetry")]
+pub mod traversal_heatmap;
 pub mod vector3;
 
 pub fn to_pixel_value(c: f64) -> u8 {
@@ -1,9 +1,8 @@
 use crate::hit::HitRecord;
 use crate::material::material::{Material, ScatterResult};
 use crate::ray::Ray;
+use crate::sampler::sampler::Sampler;
 use crate::vector3::{Color, Vector3};
-use rand::rngs::ThreadRng;
-use rand::{Rng, RngCore};
 
 #[derive(Debug, Clone)]
 pub struct Dielectric {
@@ -21,7 +20,7 @@ impl Dielectric {
 impl Material for Dielectric {
     fn scatter(
         &self,
-        rng: &mut ThreadRng,
+        sampler: &mut dyn Sampler,
         input: &Ray,
         record: &HitRecord,
     ) -> Option<ScatterResult> {
@@ -37,7 +36,7 @@ impl Material for Dielectric {
 
         let cannot_refract = refraction_ratio * sin_theta > 1.0;
         let direction =
-            if cannot_refract || reflectance(cos_theta, refraction_ratio) > rng.gen::<f64>() {
+            if cannot_refract || reflectance(cos_theta, refraction_ratio) > sampler.get_1d() {
                 unit_direction.reflect(record.normal())
             } else {
                 unit_direction.refract(record.normal(), refraction_ratio)
@@ -48,6 +47,14 @@ impl Material for Dielectric {
             Ray::new(record.point().clone(), direction, input.time()),
         ))
     }
+
+    fn albedo(&self, _record: &HitRecord) -> Color {
+        Color::white()
+    }
+
+    fn name(&self) -> &'static str {
+        "dielectric"
+    }
 }
 
 fn reflectance(cos: f64, ref_idx: f64) -> f64 {
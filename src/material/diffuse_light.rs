@@ -0,0 +1,48 @@
+use crate::hit::HitRecord;
+use crate::material::material::{Material, ScatterResult};
+use crate::ray::Ray;
+use crate::sampler::sampler::Sampler;
+use crate::texture::solid_color::SolidColor;
+use crate::texture::texture::Texture;
+use crate::vector3::Color;
+use std::sync::Arc;
+
+/// A surface that gives off light equally in every direction and reflects none.
+#[derive(Debug)]
+pub struct DiffuseLight {
+    emit: Arc<dyn Texture>,
+}
+
+impl DiffuseLight {
+    pub fn new(emit: Color) -> Self {
+        DiffuseLight {
+            emit: Arc::new(SolidColor::new(emit)),
+        }
+    }
+    pub fn new_texture(emit: Arc<dyn Texture>) -> Self {
+        DiffuseLight { emit }
+    }
+}
+
+impl Material for DiffuseLight {
+    fn scatter(
+        &self,
+        _sampler: &mut dyn Sampler,
+        _input: &Ray,
+        _record: &HitRecord,
+    ) -> Option<ScatterResult> {
+        None
+    }
+
+    fn emitted(&self, record: &HitRecord) -> Color {
+        self.emit.value(record.u(), record.v(), record.point())
+    }
+
+    fn albedo(&self, record: &HitRecord) -> Color {
+        self.emitted(record)
+    }
+
+    fn name(&self) -> &'static str {
+        "diffuse_light"
+    }
+}
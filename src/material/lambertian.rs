@@ -1,13 +1,10 @@
 use crate::hit::HitRecord;
 use crate::material::material::{Material, ScatterResult};
 use crate::ray::Ray;
+use crate::sampler::sampler::Sampler;
 use crate::texture::solid_color::SolidColor;
 use crate::texture::texture::Texture;
 use crate::vector3::{Color, Vector3};
-use rand::rngs::ThreadRng;
-use rand::{thread_rng, RngCore};
-use std::cell::RefCell;
-use std::ops::DerefMut;
 use std::sync::Arc;
 
 #[derive(Debug)]
@@ -29,11 +26,12 @@ impl Lambertian {
 impl Material for Lambertian {
     fn scatter(
         &self,
-        rng: &mut ThreadRng,
+        sampler: &mut dyn Sampler,
         input: &Ray,
         record: &HitRecord,
     ) -> Option<ScatterResult> {
-        let scatter_direction = record.normal() + &Vector3::random_unit_vector(rng);
+        let (u, v) = sampler.get_2d();
+        let scatter_direction = record.normal() + &Vector3::unit_vector_from_square(u, v);
 
         let scatter_direction = if scatter_direction.approx_zero() {
             record.normal().clone()
@@ -46,4 +44,12 @@ impl Material for Lambertian {
             Ray::new(record.point().clone(), scatter_direction, input.time()),
         ))
     }
+
+    fn albedo(&self, record: &HitRecord) -> Color {
+        self.albedo.value(record.u(), record.v(), record.point())
+    }
+
+    fn name(&self) -> &'static str {
+        "lambertian"
+    }
 }
@@ -1,8 +1,7 @@
 use crate::hit::HitRecord;
 use crate::ray::Ray;
+use crate::sampler::sampler::Sampler;
 use crate::vector3::Color;
-use rand::rngs::ThreadRng;
-use rand::RngCore;
 use std::fmt::Debug;
 
 #[derive(Debug, Clone)]
@@ -21,10 +20,69 @@ impl ScatterResult {
 }
 
 pub trait Material: Debug + Send + Sync {
+    /// Takes at most [`BSDF_DIMENSIONS_PER_BOUNCE`](crate::sampler::sampler::BSDF_DIMENSIONS_PER_BOUNCE)
+    /// values from `sampler`.
     fn scatter(
         &self,
-        rng: &mut ThreadRng,
+        sampler: &mut dyn Sampler,
         input: &Ray,
         record: &HitRecord,
     ) -> Option<ScatterResult>;
+
+    /// Light given off at `record`, black for anything that is not a light.
+    fn emitted(&self, _record: &HitRecord) -> Color {
+        Color::black()
+    }
+
+    /// The surface color at `record`, independent of lighting, as used for albedo AOVs. Black
+    /// unless the material says otherwise.
+    fn albedo(&self, _record: &HitRecord) -> Color {
+        Color::black()
+    }
+
+    /// A short name for debug output.
+    fn name(&self) -> &'static str {
+        "unknown"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector3::{Point3, Vector3};
+    use std::sync::Arc;
+
+    /// A material written against the trait as it was before albedo and names.
+    #[derive(Debug)]
+    struct Absorbing;
+
+    impl Material for Absorbing {
+        fn scatter(
+            &self,
+            _sampler: &mut dyn Sampler,
+            _input: &Ray,
+            _record: &HitRecord,
+        ) -> Option<ScatterResult> {
+            None
+        }
+    }
+
+    #[test]
+    fn only_scatter_is_required() {
+        let ray = Ray::new(Point3::zero(), Vector3::new_z(-1.0), 0.0);
+        let material: Arc<dyn Material> = Arc::new(Absorbing);
+        let record = HitRecord::new(
+            Point3::new_z(-1.0),
+            1.0,
+            0.0,
+            0.0,
+            Vector3::new_z(1.0),
+            &ray,
+            material.clone(),
+        );
+
+        assert_eq!(material.albedo(&record), Color::black());
+        assert_eq!(material.emitted(&record), Color::black());
+        assert_eq!(material.name(), "unknown");
+    }
 }
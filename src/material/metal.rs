@@ -1,11 +1,8 @@
 use crate::hit::HitRecord;
 use crate::material::material::{Material, ScatterResult};
 use crate::ray::Ray;
+use crate::sampler::sampler::Sampler;
 use crate::vector3::{Color, Vector3};
-use rand::rngs::ThreadRng;
-use rand::{thread_rng, RngCore};
-use std::cell::RefCell;
-use std::ops::DerefMut;
 
 #[derive(Debug, Clone)]
 pub struct Metal {
@@ -25,18 +22,28 @@ impl Metal {
 impl Material for Metal {
     fn scatter(
         &self,
-        rng: &mut ThreadRng,
+        sampler: &mut dyn Sampler,
         input: &Ray,
         record: &HitRecord,
     ) -> Option<ScatterResult> {
         let reflected = input.direction().unit_vector().reflect(record.normal());
+        let (u, v) = sampler.get_2d();
+        let fuzz = Vector3::in_unit_sphere_from_cube(u, v, sampler.get_1d());
         let scattered = Ray::new(
             record.point().clone(),
-            reflected + Vector3::random_in_unit_sphere(rng) * self.fuzz,
+            reflected + fuzz * self.fuzz,
             input.time(),
         );
 
         (scattered.direction().dot(record.normal()) > 0.0)
             .then(|| ScatterResult::new(self.albedo.clone(), scattered))
     }
+
+    fn albedo(&self, _record: &HitRecord) -> Color {
+        self.albedo.clone()
+    }
+
+    fn name(&self) -> &'static str {
+        "metal"
+    }
 }
@@ -1,4 +1,6 @@
 pub mod dielectric;
+pub mod diffuse_light;
 pub mod lambertian;
+#[allow(clippy::module_inception)]
 pub mod material;
 pub mod metal;
@@ -2,7 +2,7 @@ use crate::bvh::aabb::AABB;
 use crate::hit::{Hit, HitRecord};
 use crate::material::material::Material;
 use crate::ray::Ray;
-use crate::sphere::{hit_sphere, sphere_bounding_box};
+use crate::sphere::{closest_point_on_sphere, hit_sphere, sphere_bounding_box};
 use crate::vector3::Point3;
 use std::sync::Arc;
 
@@ -30,11 +30,18 @@ impl Hit for MovingSphere {
     }
 
     fn nearest_squared(&self, point: &Point3) -> f64 {
-        unimplemented!()
+        ((point - &self.closest_center(point)).length() - self.radius)
+            .max(0.0)
+            .powi(2)
     }
 
     fn farest_squared(&self, point: &Point3) -> f64 {
-        unimplemented!()
+        // The farthest point of a path that is a straight line is at one of its ends
+        let farthest_center = f64::max(
+            (point - &self.center0).length(),
+            (point - &self.center1).length(),
+        );
+        (farthest_center + self.radius).powi(2)
     }
 }
 
@@ -59,6 +66,47 @@ impl MovingSphere {
 
     pub fn center(&self, time: f64) -> Point3 {
         &self.center0
-            + (&self.center1 - &self.center0) * ((time - self.time0) / (time - self.time1))
+            + (&self.center1 - &self.center0) * ((time - self.time0) / (self.time1 - self.time0))
+    }
+
+    /// The point of the surface closest to `point` at `time`.
+    pub fn closest_point(&self, point: &Point3, time: f64) -> Point3 {
+        closest_point_on_sphere(&self.center(time), self.radius, point)
+    }
+
+    /// The point on the path of the center from `time0` to `time1` that is closest to `point`.
+    fn closest_center(&self, point: &Point3) -> Point3 {
+        let path = &self.center1 - &self.center0;
+        let length_squared = path.length_squared();
+        let s = if length_squared > 0.0 {
+            ((point - &self.center0).dot(&path) / length_squared).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        &self.center0 + path * s
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::lambertian::Lambertian;
+    use crate::vector3::Color;
+
+    #[test]
+    fn distances_cover_the_whole_path() {
+        let material = Arc::new(Lambertian::new(Color::white()));
+        let sphere = MovingSphere::new(Point3::zero(), Point3::new_x(4.0), 1.0, 0.0, 1.0, material);
+
+        assert_eq!(sphere.center(0.5), Point3::new_x(2.0));
+        // Closest to the middle of the path, farthest from both ends
+        let above = Point3::new(2.0, 3.0, 0.0);
+        assert_eq!(sphere.nearest_squared(&above), 4.0);
+        assert!((sphere.farest_squared(&above) - (13f64.sqrt() + 1.0).powi(2)).abs() < 1e-12);
+        // Past the start, where the far end is the end of the path
+        let behind = Point3::new_x(-3.0);
+        assert_eq!(sphere.nearest_squared(&behind), 4.0);
+        assert_eq!(sphere.farest_squared(&behind), 64.0);
+        assert_eq!(sphere.nearest_squared(&Point3::new_x(3.5)), 0.0);
     }
 }
@@ -0,0 +1,394 @@
+use crate::adaptive::PixelEstimate;
+use crate::camera::Camera;
+use crate::environment::environment::Environment;
+use crate::hit::Hit;
+use crate::render::{camera_ray, max_samples, sample_batches, RenderSettings};
+use crate::sampler::sampler::{BSDF_DIMENSION, BSDF_DIMENSIONS_PER_BOUNCE};
+use crate::vector3::{Color, Point3, Vector3};
+use std::fmt;
+use std::io::{self, Write};
+
+/// One surface a path hit.
+#[derive(Debug, Clone)]
+pub struct Bounce {
+    pub point: Point3,
+    pub normal: Vector3,
+    pub front_face: bool,
+    pub t: f64,
+    pub object_index: Option<usize>,
+    pub material: &'static str,
+    /// Black unless the surface is a light.
+    pub emitted: Color,
+    /// `None` when the material absorbed the path.
+    pub attenuation: Option<Color>,
+    pub scattered: Option<Vector3>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PathEnd {
+    /// Left the scene, picking up this much light from the environment.
+    Escaped(Color),
+    /// A material absorbed it, as lights do.
+    Absorbed,
+    /// Cut off after `max_depth` bounces.
+    MaxDepth,
+}
+
+/// Everything that happened to one camera sample, exactly as the renderer traces it.
+#[derive(Debug, Clone)]
+pub struct PathLog {
+    /// Column and row from the top.
+    pub pixel: (usize, usize),
+    pub sample_index: usize,
+    pub origin: Point3,
+    pub direction: Vector3,
+    pub bounces: Vec<Bounce>,
+    pub end: PathEnd,
+    /// What the sample adds to the pixel.
+    pub radiance: Color,
+}
+
+/// Retraces sample `sample_index` of the pixel at column `pixel.0` and row `pixel.1` from the top.
+/// The sampler, seed and sample numbering come from `settings`, so the path is the one
+/// [`render`](crate::render::render) took for that sample.
+pub fn inspect_path(
+    world: &dyn Hit,
+    camera: &Camera,
+    environment: &dyn Environment,
+    settings: &RenderSettings,
+    pixel: (usize, usize),
+    sample_index: usize,
+) -> PathLog {
+    let width = settings.image_width;
+    let height = settings.image_height;
+    let mut sampler = settings.sampler.build(max_samples(settings), settings.seed);
+    let position = (pixel.0 as f64, (height - 1 - pixel.1) as f64);
+    let (mut ray, _) = camera_ray(
+        sampler.as_mut(),
+        camera,
+        pixel,
+        sample_index,
+        position,
+        (width, height),
+    );
+    let (origin, direction) = (ray.origin().clone(), ray.direction().clone());
+
+    let mut bounces = Vec::new();
+    let mut depth = settings.max_depth;
+    let end = loop {
+        if depth == 0 {
+            break PathEnd::MaxDepth;
+        }
+
+        let record = match world.hit(&ray, 0.001, f64::INFINITY) {
+            Some(record) => record,
+            None => break PathEnd::Escaped(environment.value(ray.direction())),
+        };
+
+        sampler.set_dimension(BSDF_DIMENSION + bounces.len() * BSDF_DIMENSIONS_PER_BOUNCE);
+        let scattered = record.material().scatter(sampler.as_mut(), &ray, &record);
+        bounces.push(Bounce {
+            point: record.point().clone(),
+            normal: record.normal().clone(),
+            front_face: record.front_face(),
+            t: record.t(),
+            object_index: record.object_index(),
+            material: record.material().name(),
+            emitted: record.material().emitted(&record),
+            attenuation: scattered.as_ref().map(|s| s.attenuation.clone()),
+            scattered: scattered.as_ref().map(|s| s.scattered.direction().clone()),
+        });
+
+        match scattered {
+            Some(result) => ray = result.scattered,
+            None => break PathEnd::Absorbed,
+        }
+        depth -= 1;
+    };
+
+    // Multiplied from the light back to the camera, in the same order as the renderer
+    let light = match &end {
+        PathEnd::Escaped(color) => Vector3::from(color.clone()),
+        _ => Vector3::zero(),
+    };
+    let radiance = bounces.iter().rev().fold(light, |radiance, bounce| {
+        Vector3::from(bounce.emitted.clone())
+            + match &bounce.attenuation {
+                Some(a) => Vector3::from(a.clone()).hadamard_product(&radiance),
+                None => Vector3::zero(),
+            }
+    });
+
+    PathLog {
+        pixel,
+        sample_index,
+        origin,
+        direction,
+        bounces,
+        end,
+        radiance: Color::from(radiance),
+    }
+}
+
+/// Every sample of a pixel, for finding the one behind a firefly. With adaptive sampling these
+/// are the samples the renderer took before the pixel converged.
+pub fn inspect_pixel(
+    world: &dyn Hit,
+    camera: &Camera,
+    environment: &dyn Environment,
+    settings: &RenderSettings,
+    pixel: (usize, usize),
+) -> Vec<PathLog> {
+    let max_samples = max_samples(settings);
+    let (first_batch, batch_size) = sample_batches(settings);
+    let mut estimate = PixelEstimate::new();
+    let mut paths = Vec::new();
+    let mut batch = first_batch;
+
+    // The same batches and stopping rule as render_pixel
+    loop {
+        for index in paths.len()..paths.len() + batch {
+            let path = inspect_path(world, camera, environment, settings, pixel, index);
+            estimate.add(&Vector3::from(path.radiance.clone()));
+            paths.push(path);
+        }
+
+        let converged = settings
+            .adaptive
+            .map(|a| estimate.relative_error() <= a.threshold)
+            .unwrap_or(true);
+        if converged || paths.len() >= max_samples {
+            break;
+        }
+        batch = batch_size.min(max_samples - paths.len());
+    }
+    paths
+}
+
+impl PathLog {
+    /// The points the path runs through, ending `escape_length` out along the last ray if it
+    /// escaped.
+    pub fn vertices(&self, escape_length: f64) -> Vec<Point3> {
+        let mut vertices = vec![self.origin.clone()];
+        vertices.extend(self.bounces.iter().map(|b| b.point.clone()));
+
+        if let PathEnd::Escaped(_) = self.end {
+            let (from, direction) = match self.bounces.last() {
+                Some(b) => (b.point.clone(), b.scattered.clone().unwrap()),
+                None => (self.origin.clone(), self.direction.clone()),
+            };
+            vertices.push(&from + &(direction.unit_vector() * escape_length));
+        }
+        vertices
+    }
+}
+
+/// Writes `paths` as OBJ polylines, one object per path, to view alongside the scene.
+pub fn write_obj<W: Write>(out: &mut W, paths: &[PathLog], escape_length: f64) -> io::Result<()> {
+    let mut first_vertex = 1;
+    for path in paths {
+        let vertices = path.vertices(escape_length);
+        writeln!(
+            out,
+            "o path_{}_{}_sample_{}",
+            path.pixel.0, path.pixel.1, path.sample_index
+        )?;
+        for v in &vertices {
+            writeln!(out, "v {} {} {}", v.x(), v.y(), v.z())?;
+        }
+        let indices: Vec<_> = (first_vertex..first_vertex + vertices.len())
+            .map(|i| i.to_string())
+            .collect();
+        writeln!(out, "l {}", indices.join(" "))?;
+        first_vertex += vertices.len();
+    }
+    Ok(())
+}
+
+fn triple(x: f64, y: f64, z: f64) -> String {
+    format!("({:.4}, {:.4}, {:.4})", x, y, z)
+}
+
+fn vector(v: &Vector3) -> String {
+    triple(v.x(), v.y(), v.z())
+}
+
+fn color(c: &Color) -> String {
+    triple(c.r(), c.g(), c.b())
+}
+
+impl fmt::Display for PathLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "pixel ({}, {}) sample {}: radiance {}",
+            self.pixel.0,
+            self.pixel.1,
+            self.sample_index,
+            color(&self.radiance)
+        )?;
+        writeln!(
+            f,
+            "  camera ray from {} towards {}",
+            vector(self.origin.as_vector()),
+            vector(&self.direction)
+        )?;
+
+        for (i, b) in self.bounces.iter().enumerate() {
+            let object = b
+                .object_index
+                .map_or_else(|| "?".to_string(), |i| i.to_string());
+            writeln!(
+                f,
+                "  bounce {}: object {} ({}) at t = {:.4}, point {}, normal {}, {} face",
+                i,
+                object,
+                b.material,
+                b.t,
+                vector(b.point.as_vector()),
+                vector(&b.normal),
+                if b.front_face { "front" } else { "back" }
+            )?;
+            if b.emitted != Color::black() {
+                writeln!(f, "    emitted {}", color(&b.emitted))?;
+            }
+            if let (Some(a), Some(s)) = (&b.attenuation, &b.scattered) {
+                writeln!(
+                    f,
+                    "    attenuation {}, scattered towards {}",
+                    color(a),
+                    vector(s)
+                )?;
+            }
+        }
+
+        match &self.end {
+            PathEnd::Escaped(c) => writeln!(f, "  ended: escaped, environment {}", color(c)),
+            PathEnd::Absorbed => writeln!(f, "  ended: absorbed"),
+            PathEnd::MaxDepth => writeln!(f, "  ended: reached max depth"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adaptive::AdaptiveSampling;
+    use crate::environment::gradient_sky::GradientSky;
+    use crate::hit_objects::{HitObject, HitObjects};
+    use crate::material::lambertian::Lambertian;
+    use crate::material::metal::Metal;
+    use crate::render::{render, render_with_sample_counts};
+    use crate::sampler::sampler::SamplerKind;
+    use crate::sphere::Sphere;
+    use std::sync::Arc;
+
+    fn world() -> HitObjects {
+        let mut world = HitObjects::new();
+        world.add(HitObject::Sphere(Sphere::new(
+            Point3::new_z(-1.0),
+            0.5,
+            Arc::new(Metal::new(Color::new(0.8, 0.8, 0.8), 0.3)),
+        )));
+        world.add(HitObject::Sphere(Sphere::new(
+            Point3::new_y(-100.5),
+            100.0,
+            Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        )));
+        world
+    }
+
+    #[test]
+    fn inspected_samples_add_up_to_the_rendered_pixel() {
+        let world = world();
+        let camera = Camera::default();
+        let sky = GradientSky::default();
+        let mut settings = RenderSettings::new(8, 6);
+        settings.samples_per_pixel = 4;
+        settings.sampler = SamplerKind::Sobol;
+        settings.seed = 7;
+
+        let image = render(&world, &camera, &sky, &settings);
+        for pixel in [(4, 2), (1, 5)] {
+            let paths = inspect_pixel(&world, &camera, &sky, &settings, pixel);
+            let sum = paths.iter().fold(Vector3::zero(), |sum, p| {
+                sum + Vector3::from(p.radiance.clone())
+            });
+            let mean = Color::from(sum / 4.0);
+            let rendered = image.pixel(pixel.0, pixel.1);
+            assert!((mean.r() - rendered.r()).abs() < 1e-12, "{:?}", paths);
+            assert!((mean.b() - rendered.b()).abs() < 1e-12, "{:?}", paths);
+        }
+    }
+
+    #[test]
+    fn adaptive_pixels_inspect_the_samples_they_took() {
+        let world = world();
+        let camera = Camera::default();
+        let sky = GradientSky::default();
+        let mut settings = RenderSettings::new(8, 6);
+        settings.adaptive = Some(AdaptiveSampling {
+            min_samples: 4,
+            max_samples: 64,
+            batch_size: 4,
+            threshold: 0.05,
+        });
+
+        let (image, counts) = render_with_sample_counts(&world, &camera, &sky, &settings);
+        assert!(counts.counts().iter().any(|&c| c != counts.get(0, 0)));
+        for pixel in [(4, 2), (1, 5), (0, 0)] {
+            let paths = inspect_pixel(&world, &camera, &sky, &settings, pixel);
+            assert_eq!(paths.len(), counts.get(pixel.0, pixel.1));
+            let sum = paths.iter().fold(Vector3::zero(), |sum, p| {
+                sum + Vector3::from(p.radiance.clone())
+            });
+            let mean = Color::from(sum / paths.len() as f64);
+            let rendered = image.pixel(pixel.0, pixel.1);
+            assert!((mean.g() - rendered.g()).abs() < 1e-12, "{:?}", paths);
+        }
+    }
+
+    #[test]
+    fn no_depth_traces_nothing() {
+        let world = world();
+        let camera = Camera::default();
+        let sky = GradientSky::default();
+        let mut settings = RenderSettings::new(8, 6);
+        settings.samples_per_pixel = 2;
+        settings.max_depth = 0;
+
+        let path = inspect_path(&world, &camera, &sky, &settings, (4, 2), 0);
+        assert!(path.bounces.is_empty());
+        assert_eq!(path.end, PathEnd::MaxDepth);
+        assert_eq!(path.radiance, Color::black());
+        assert_eq!(
+            render(&world, &camera, &sky, &settings).pixel(4, 2),
+            &Color::black()
+        );
+    }
+
+    #[test]
+    fn paths_export_as_polylines() {
+        let world = world();
+        let mut settings = RenderSettings::new(8, 6);
+        settings.max_depth = 3;
+        let path = inspect_path(
+            &world,
+            &Camera::default(),
+            &GradientSky::default(),
+            &settings,
+            (4, 2),
+            0,
+        );
+        assert_eq!(path.bounces[0].material, "metal");
+        assert!(path.bounces.len() <= 3);
+        assert!(path.to_string().contains("bounce 0: object 0 (metal)"));
+
+        let mut obj = Vec::new();
+        write_obj(&mut obj, &[path.clone(), path.clone()], 10.0).unwrap();
+        let obj = String::from_utf8(obj).unwrap();
+        let n = path.vertices(10.0).len();
+        assert_eq!(obj.matches("\nv ").count(), 2 * n);
+        assert!(obj.contains(&format!("l {} ", n + 1)));
+    }
+}
@@ -0,0 +1,80 @@
+use crate::camera::Camera;
+use crate::hit::{Hit, HitRecord};
+use crate::render::center_ray;
+
+/// What the center of a pixel shows.
+#[derive(Debug, Clone)]
+pub struct Pick {
+    /// Index into the [`HitObjects`](crate::hit_objects::HitObjects) the world, or the BVH it
+    /// was built from, holds. `None` if the world is a lone object.
+    pub object_index: Option<usize>,
+    /// The point, normal, surface coordinates and material at the hit.
+    pub record: HitRecord,
+}
+
+/// Casts the ray through the center of the pixel at column `pixel.0` and row `pixel.1` from the
+/// top of an image of `image_size`, framed as the renderer frames it, and returns the first
+/// surface it hits.
+pub fn pick(
+    world: &dyn Hit,
+    camera: &Camera,
+    image_size: (usize, usize),
+    pixel: (usize, usize),
+) -> Option<Pick> {
+    let ray = center_ray(camera, image_size, pixel);
+    world.hit(&ray, 0.001, f64::INFINITY).map(|record| Pick {
+        object_index: record.object_index(),
+        record,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bvh::node::Node;
+    use crate::hit_objects::{HitObject, HitObjects};
+    use crate::material::lambertian::Lambertian;
+    use crate::material::metal::Metal;
+    use crate::sphere::Sphere;
+    use crate::vector3::{Color, Point3, Vector3};
+    use rand::thread_rng;
+    use std::sync::Arc;
+
+    #[test]
+    fn picks_the_object_under_the_pixel() {
+        let mut world = HitObjects::new();
+        world.add(HitObject::Sphere(Sphere::new(
+            Point3::new(-1.0, 0.0, -3.0),
+            0.8,
+            Arc::new(Lambertian::new(Color::red())),
+        )));
+        world.add(HitObject::Sphere(Sphere::new(
+            Point3::new(1.0, 0.0, -3.0),
+            0.8,
+            Arc::new(Metal::new(Color::white(), 0.0)),
+        )));
+        let camera = Camera::new(
+            Point3::zero(),
+            Point3::new_z(-1.0),
+            Vector3::new_y(1.0),
+            90.0,
+            2.0,
+            0.0,
+            1.0,
+            0.0,
+            1.0,
+        );
+        let bvh = Node::new(&mut thread_rng(), &world.0, 0.0, 1.0).unwrap();
+
+        for world in [&world as &dyn Hit, &bvh] {
+            let left = pick(world, &camera, (40, 20), (15, 10)).unwrap();
+            assert_eq!(left.object_index, Some(0));
+            assert_eq!(left.record.material().name(), "lambertian");
+            assert!(left.record.normal().z() > 0.0);
+
+            let right = pick(world, &camera, (40, 20), (24, 10)).unwrap();
+            assert_eq!(right.object_index, Some(1));
+            assert!(pick(world, &camera, (40, 20), (20, 0)).is_none());
+        }
+    }
+}
@@ -0,0 +1,543 @@
+//! A minimal PNG encoder: 8-bit RGB, no filtering, and deflate's stored blocks in place of
+//! compression. The files are large but need nothing beyond the standard library. The decoder
+//! reads what other tools write: any filtering and compression, 8 or 16 bits per channel, but
+//! not interlacing.
+
+use crate::image::invalid_data;
+use std::io;
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+const MAX_STORED_BLOCK: usize = 65535;
+
+/// Encodes `rgb`, three bytes per pixel, row by row from the top.
+pub fn encode(width: usize, height: usize, rgb: &[u8]) -> Vec<u8> {
+    assert_eq!(rgb.len(), width * height * 3);
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(width as u32).to_be_bytes());
+    header.extend_from_slice(&(height as u32).to_be_bytes());
+    // 8 bits per channel, RGB, deflate, no filtering, no interlacing
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+    let mut scanlines = Vec::with_capacity(height * (width * 3 + 1));
+    for row in rgb.chunks(width * 3) {
+        scanlines.push(0);
+        scanlines.extend_from_slice(row);
+    }
+
+    let mut png = SIGNATURE.to_vec();
+    write_chunk(&mut png, b"IHDR", &header);
+    write_chunk(&mut png, b"IDAT", &zlib_stored(&scanlines));
+    write_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+/// Decodes a PNG into three bytes per pixel, row by row from the top. Grayscale is widened to
+/// RGB, alpha is dropped and 16-bit channels keep their high byte.
+pub fn decode(png: &[u8]) -> io::Result<(usize, usize, Vec<u8>)> {
+    if png.len() < SIGNATURE.len() || png[..SIGNATURE.len()] != SIGNATURE {
+        return Err(invalid_data("not a PNG file"));
+    }
+
+    let mut header = None;
+    let mut palette = Vec::new();
+    let mut compressed = Vec::new();
+    let mut rest = &png[SIGNATURE.len()..];
+    while rest.len() >= 12 {
+        let len = u32::from_be_bytes([rest[0], rest[1], rest[2], rest[3]]) as usize;
+        if rest.len() < 12 + len {
+            break;
+        }
+        let kind = &rest[4..8];
+        let data = &rest[8..8 + len];
+        match kind {
+            b"IHDR" if len == 13 => header = Some(data.to_vec()),
+            b"PLTE" => palette = data.to_vec(),
+            b"IDAT" => compressed.extend_from_slice(data),
+            b"IEND" => break,
+            _ => {}
+        }
+        rest = &rest[12 + len..];
+    }
+
+    let header = header.ok_or_else(|| invalid_data("PNG has no header"))?;
+    let width = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+    let height = u32::from_be_bytes([header[4], header[5], header[6], header[7]]) as usize;
+    let (depth, color_type, interlace) = (header[8], header[9], header[12]);
+    let channels = match (color_type, depth) {
+        (0, 8) | (0, 16) => 1,
+        (2, 8) | (2, 16) => 3,
+        (3, 8) => 1,
+        (4, 8) | (4, 16) => 2,
+        (6, 8) | (6, 16) => 4,
+        _ => return Err(invalid_data("unsupported PNG color type or bit depth")),
+    };
+    if interlace != 0 {
+        return Err(invalid_data("interlaced PNGs are not supported"));
+    }
+    if width == 0 || height == 0 {
+        return Err(invalid_data("PNG image has no pixels"));
+    }
+
+    // The sizes come from the file, so they are checked before anything is allocated for them
+    let too_large = || invalid_data("PNG image is too large");
+    let bytes_per_pixel = channels * depth as usize / 8;
+    let stride = width.checked_mul(bytes_per_pixel).ok_or_else(too_large)?;
+    let data_size = stride
+        .checked_add(1)
+        .and_then(|row| row.checked_mul(height))
+        .ok_or_else(too_large)?;
+    let rgb_size = width
+        .checked_mul(height)
+        .and_then(|n| n.checked_mul(3))
+        .ok_or_else(too_large)?;
+    let scanlines = inflate(&compressed)?;
+    if scanlines.len() < data_size {
+        return Err(invalid_data("PNG image data is truncated"));
+    }
+    let samples = unfilter(&scanlines, height, stride, bytes_per_pixel)?;
+
+    let mut rgb = Vec::with_capacity(rgb_size);
+    for pixel in samples.chunks(bytes_per_pixel) {
+        // The high byte of each channel
+        let channel = |i: usize| pixel[i * depth as usize / 8];
+        match color_type {
+            0 | 4 => rgb.extend_from_slice(&[channel(0); 3]),
+            3 => {
+                let entry = 3 * pixel[0] as usize;
+                let color = palette
+                    .get(entry..entry + 3)
+                    .ok_or_else(|| invalid_data("PNG palette index out of range"))?;
+                rgb.extend_from_slice(color);
+            }
+            _ => rgb.extend_from_slice(&[channel(0), channel(1), channel(2)]),
+        }
+    }
+    Ok((width, height, rgb))
+}
+
+/// Undoes the per-row filters, returning the raw samples without the filter type bytes.
+fn unfilter(
+    scanlines: &[u8],
+    height: usize,
+    stride: usize,
+    bytes_per_pixel: usize,
+) -> io::Result<Vec<u8>> {
+    let mut out = vec![0u8; height * stride];
+    for row in 0..height {
+        let filter = scanlines[row * (stride + 1)];
+        let line = &scanlines[row * (stride + 1) + 1..(row + 1) * (stride + 1)];
+        let (done, current) = out.split_at_mut(row * stride);
+        let previous = if row > 0 {
+            &done[(row - 1) * stride..]
+        } else {
+            &[][..]
+        };
+        let current = &mut current[..stride];
+
+        for i in 0..stride {
+            let left = if i >= bytes_per_pixel {
+                current[i - bytes_per_pixel]
+            } else {
+                0
+            };
+            let up = previous.get(i).copied().unwrap_or(0);
+            let up_left = if i >= bytes_per_pixel {
+                previous.get(i - bytes_per_pixel).copied().unwrap_or(0)
+            } else {
+                0
+            };
+            let predicted = match filter {
+                0 => 0,
+                1 => left,
+                2 => up,
+                3 => ((left as u16 + up as u16) / 2) as u8,
+                4 => paeth(left, up, up_left),
+                _ => return Err(invalid_data("unknown PNG filter type")),
+            };
+            current[i] = line[i].wrapping_add(predicted);
+        }
+    }
+    Ok(out)
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = (
+        (p - a as i16).abs(),
+        (p - b as i16).abs(),
+        (p - c as i16).abs(),
+    );
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+/// The order in which a dynamic block lists the code lengths of its code length alphabet.
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// Reads deflate's bit stream, least significant bit first.
+struct Bits<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl Bits<'_> {
+    fn read(&mut self, count: u8) -> io::Result<u32> {
+        let mut value = 0;
+        for i in 0..count {
+            let byte = self
+                .data
+                .get(self.position / 8)
+                .ok_or_else(|| invalid_data("compressed data is truncated"))?;
+            value |= (((byte >> (self.position % 8)) & 1) as u32) << i;
+            self.position += 1;
+        }
+        Ok(value)
+    }
+
+    fn align_to_byte(&mut self) {
+        self.position = self.position.div_ceil(8) * 8;
+    }
+}
+
+/// A canonical Huffman code, stored as the number of codes of each length and the symbols in
+/// code order.
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Self {
+        let mut counts = [0u16; 16];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        counts[0] = 0;
+
+        let mut symbols = Vec::with_capacity(lengths.len());
+        for length in 1..16 {
+            for (symbol, _) in lengths.iter().enumerate().filter(|(_, &l)| l == length) {
+                symbols.push(symbol as u16);
+            }
+        }
+        Huffman { counts, symbols }
+    }
+
+    fn decode(&self, bits: &mut Bits) -> io::Result<u16> {
+        // Codes of each length follow on from the last code of the length before
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for &count in &self.counts[1..] {
+            code |= bits.read(1)? as i32;
+            let count = count as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(invalid_data("invalid Huffman code"))
+    }
+}
+
+/// Decompresses a zlib stream, without checking its checksum.
+fn inflate(zlib: &[u8]) -> io::Result<Vec<u8>> {
+    if zlib.len() < 2
+        || zlib[0] & 0x0f != 8
+        || !(zlib[0] as u16 * 256 + zlib[1] as u16).is_multiple_of(31)
+    {
+        return Err(invalid_data("not a zlib stream"));
+    }
+    if zlib[1] & 0x20 != 0 {
+        return Err(invalid_data("zlib preset dictionaries are not supported"));
+    }
+
+    let mut bits = Bits {
+        data: &zlib[2..],
+        position: 0,
+    };
+    let mut out = Vec::new();
+    loop {
+        let last = bits.read(1)? == 1;
+        match bits.read(2)? {
+            0 => {
+                bits.align_to_byte();
+                let start = bits.position / 8;
+                let header = bits
+                    .data
+                    .get(start..start + 4)
+                    .ok_or_else(|| invalid_data("compressed data is truncated"))?;
+                let len = u16::from_le_bytes([header[0], header[1]]) as usize;
+                let block = bits
+                    .data
+                    .get(start + 4..start + 4 + len)
+                    .ok_or_else(|| invalid_data("compressed data is truncated"))?;
+                out.extend_from_slice(block);
+                bits.position = (start + 4 + len) * 8;
+            }
+            1 => {
+                let mut lengths = [0u8; 288];
+                lengths[..144].fill(8);
+                lengths[144..256].fill(9);
+                lengths[256..280].fill(7);
+                lengths[280..].fill(8);
+                let literals = Huffman::new(&lengths);
+                let distances = Huffman::new(&[5; 30]);
+                inflate_block(&mut bits, &literals, &distances, &mut out)?;
+            }
+            2 => {
+                let (literals, distances) = dynamic_codes(&mut bits)?;
+                inflate_block(&mut bits, &literals, &distances, &mut out)?;
+            }
+            _ => return Err(invalid_data("invalid deflate block type")),
+        }
+        if last {
+            return Ok(out);
+        }
+    }
+}
+
+fn dynamic_codes(bits: &mut Bits) -> io::Result<(Huffman, Huffman)> {
+    let literal_count = bits.read(5)? as usize + 257;
+    let distance_count = bits.read(5)? as usize + 1;
+    let code_length_count = bits.read(4)? as usize + 4;
+
+    let mut code_lengths = [0u8; 19];
+    for &i in &CODE_LENGTH_ORDER[..code_length_count] {
+        code_lengths[i] = bits.read(3)? as u8;
+    }
+    let code_lengths = Huffman::new(&code_lengths);
+
+    let mut lengths = Vec::with_capacity(literal_count + distance_count);
+    while lengths.len() < literal_count + distance_count {
+        let (value, repeat) = match code_lengths.decode(bits)? {
+            symbol @ 0..=15 => (symbol as u8, 1),
+            16 => {
+                let previous = *lengths
+                    .last()
+                    .ok_or_else(|| invalid_data("invalid deflate code lengths"))?;
+                (previous, 3 + bits.read(2)? as usize)
+            }
+            17 => (0, 3 + bits.read(3)? as usize),
+            _ => (0, 11 + bits.read(7)? as usize),
+        };
+        lengths.extend(std::iter::repeat_n(value, repeat));
+    }
+    if lengths.len() > literal_count + distance_count {
+        return Err(invalid_data("invalid deflate code lengths"));
+    }
+
+    Ok((
+        Huffman::new(&lengths[..literal_count]),
+        Huffman::new(&lengths[literal_count..]),
+    ))
+}
+
+fn inflate_block(
+    bits: &mut Bits,
+    literals: &Huffman,
+    distances: &Huffman,
+    out: &mut Vec<u8>,
+) -> io::Result<()> {
+    loop {
+        let symbol = literals.decode(bits)? as usize;
+        if symbol < 256 {
+            out.push(symbol as u8);
+            continue;
+        }
+        if symbol == 256 {
+            return Ok(());
+        }
+
+        let i = symbol - 257;
+        if i >= LENGTH_BASE.len() {
+            return Err(invalid_data("invalid deflate length"));
+        }
+        let length = LENGTH_BASE[i] as usize + bits.read(LENGTH_EXTRA[i])? as usize;
+        let j = distances.decode(bits)? as usize;
+        if j >= DISTANCE_BASE.len() {
+            return Err(invalid_data("invalid deflate distance"));
+        }
+        let distance = DISTANCE_BASE[j] as usize + bits.read(DISTANCE_EXTRA[j])? as usize;
+        if distance > out.len() {
+            return Err(invalid_data("deflate distance reaches before the start"));
+        }
+
+        // The copy may overlap what it writes, repeating the last `distance` bytes
+        let start = out.len() - distance;
+        for k in 0..length {
+            out.push(out[start + k]);
+        }
+    }
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    // Deflate with a 32K window and no preset dictionary, at the fastest level
+    let mut out = vec![0x78, 0x01];
+
+    let mut blocks = data.chunks(MAX_STORED_BLOCK).peekable();
+    if blocks.peek().is_none() {
+        out.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        let len = block.len() as u16;
+        out.push(u8::from(last));
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xedb8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for byte in chunk {
+            a += *byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checksums_match_known_values() {
+        assert_eq!(crc32(b"IEND"), 0xae42_6082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+    }
+
+    #[test]
+    fn large_images_are_split_into_stored_blocks() {
+        let (width, height) = (200, 200);
+        let png = encode(width, height, &vec![7; width * height * 3]);
+
+        assert_eq!(&png[..8], &SIGNATURE);
+        assert_eq!(
+            &png[png.len() - 12..],
+            &[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xae, 0x42, 0x60, 0x82]
+        );
+
+        let raw = height * (width * 3 + 1);
+        let blocks = raw.div_ceil(MAX_STORED_BLOCK);
+        let idat_len = u32::from_be_bytes([png[33], png[34], png[35], png[36]]) as usize;
+        assert_eq!(idat_len, 2 + raw + 5 * blocks + 4);
+    }
+
+    #[test]
+    fn decodes_its_own_output_and_filtered_compressed_images() {
+        let rgb: Vec<u8> = (0..4 * 5 * 3).map(|i| (i * 7) as u8).collect();
+        assert_eq!(decode(&encode(4, 5, &rgb)).unwrap(), (4, 5, rgb));
+
+        // 4 by 5 pixels, written with zlib at level 9 and each row filtered differently
+        let png = [
+            137, 80, 78, 71, 13, 10, 26, 10, 0, 0, 0, 13, 73, 72, 68, 82, 0, 0, 0, 4, 0, 0, 0, 5,
+            8, 2, 0, 0, 0, 237, 207, 218, 140, 0, 0, 0, 50, 73, 68, 65, 84, 120, 218, 99, 96, 96,
+            248, 111, 195, 123, 189, 66, 106, 253, 22, 245, 118, 70, 17, 183, 111, 54, 188, 55, 32,
+            136, 73, 196, 237, 59, 28, 49, 107, 244, 228, 105, 104, 189, 128, 32, 22, 144, 24, 47,
+            20, 1, 0, 5, 245, 24, 45, 87, 43, 43, 211, 0, 0, 0, 0, 73, 69, 78, 68, 174, 66, 96,
+            130,
+        ];
+        let (width, height, rgb) = decode(&png).unwrap();
+        assert_eq!((width, height), (4, 5));
+        for y in 0..5 {
+            for x in 0..4 {
+                let expected = [
+                    (x * 60 + y * 20) % 256,
+                    (x * 13 + y * 70) % 256,
+                    255 - x * 40 - y * 9,
+                ];
+                let i = 3 * (y * 4 + x);
+                assert_eq!(&rgb[i..i + 3], &expected.map(|c| c as u8));
+            }
+        }
+    }
+
+    #[test]
+    fn empty_and_oversized_headers_are_invalid_data() {
+        // 16-bit RGBA, so the largest sizes overflow the scanline size on any target
+        for (width, height) in [(0u32, 5u32), (4, 0), (u32::MAX, u32::MAX)] {
+            let mut header = width.to_be_bytes().to_vec();
+            header.extend_from_slice(&height.to_be_bytes());
+            header.extend_from_slice(&[16, 6, 0, 0, 0]);
+            let mut png = SIGNATURE.to_vec();
+            write_chunk(&mut png, b"IHDR", &header);
+            write_chunk(&mut png, b"IDAT", &zlib_stored(&[0; 16]));
+            write_chunk(&mut png, b"IEND", &[]);
+
+            let error = decode(&png).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn inflates_dynamic_huffman_blocks() {
+        let zlib = [
+            120, 218, 205, 203, 219, 13, 128, 32, 16, 68, 209, 86, 166, 2, 27, 176, 26, 80, 148,
+            85, 97, 17, 121, 8, 213, 187, 177, 7, 19, 63, 39, 247, 76, 178, 6, 103, 166, 105, 135,
+            142, 92, 61, 22, 190, 177, 101, 23, 46, 112, 49, 17, 73, 242, 161, 122, 195, 204, 235,
+            248, 174, 31, 224, 160, 196, 185, 6, 45, 168, 82, 178, 88, 168, 24, 73, 221, 120, 28,
+            116, 102, 142, 242, 93, 175, 225, 3, 248, 0, 118, 88, 109, 214,
+        ];
+        let expected = [
+            "the quick brown fox jumps over the lazy dog; ".repeat(4),
+            "pack my box with five dozen liquor jugs. ".repeat(3),
+        ]
+        .concat();
+        let inflated = inflate(&zlib).unwrap();
+        assert_eq!(inflated, expected.as_bytes());
+        assert_eq!(adler32(&inflated).to_be_bytes(), zlib[zlib.len() - 4..]);
+    }
+}
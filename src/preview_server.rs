@@ -0,0 +1,256 @@
+//! Serves the state of a render in progress to a browser on this machine.
+//!
+//! * `/` is a page that keeps the picture and the status up to date.
+//! * `/image.png` is the latest image, tone mapped.
+//! * `/status.json` is the latest [`PreviewStatus`].
+
+use crate::image::Image;
+use crate::tone_mapping::ToneMapping;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+const PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head><title>Render preview</title></head>
+<body style="background: #222; color: #ddd; font-family: monospace">
+<p id="status">waiting for the first pass</p>
+<img id="image" src="/image.png">
+<script>
+setInterval(async () => {
+  const s = await (await fetch("/status.json")).json();
+  const eta = s.eta_seconds === null ? "-" : s.eta_seconds.toFixed(1) + " s";
+  document.getElementById("status").textContent =
+    `${s.passes} passes, ${s.samples_per_pixel} spp, ETA ${eta}, ` +
+    (s.rays_per_second === null
+      ? `${((s.samples_per_second ?? 0) / 1e6).toFixed(2)} Msamples/s`
+      : `${(s.rays_per_second / 1e6).toFixed(2)} Mrays/s`);
+  document.getElementById("image").src = "/image.png?" + s.passes;
+}, 1000);
+</script>
+</body>
+</html>
+"#;
+
+/// How far the render has come.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct PreviewStatus {
+    pub passes: usize,
+    pub samples_per_pixel: usize,
+    pub eta: Option<Duration>,
+    /// Camera samples, each a whole path however many rays it traces.
+    pub samples_per_second: f64,
+    /// Rays traced, from the camera and scattered off surfaces, as counted by
+    /// [`telemetry`](crate::telemetry). `None` when the counts are not available.
+    pub rays_per_second: Option<f64>,
+}
+
+impl PreviewStatus {
+    pub fn to_json(&self) -> String {
+        format!(
+            concat!(
+                r#"{{"passes":{},"samples_per_pixel":{},"eta_seconds":{},"#,
+                r#""samples_per_second":{},"rays_per_second":{}}}"#
+            ),
+            self.passes,
+            self.samples_per_pixel,
+            json_number(self.eta.map(|eta| eta.as_secs_f64())),
+            json_number(Some(self.samples_per_second)),
+            json_number(self.rays_per_second)
+        )
+    }
+}
+
+/// JSON has no NaN or infinity, so those are written as `null` like missing values.
+fn json_number(value: Option<f64>) -> String {
+    match value {
+        Some(v) if v.is_finite() => v.to_string(),
+        _ => "null".to_string(),
+    }
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    png: Mutex<Arc<Vec<u8>>>,
+    status: Mutex<PreviewStatus>,
+    shutdown: AtomicBool,
+}
+
+/// An HTTP server on `127.0.0.1`, answering from a background thread until dropped.
+#[derive(Debug)]
+pub struct PreviewServer {
+    address: SocketAddr,
+    shared: Arc<Shared>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl PreviewServer {
+    /// Port 0 picks a free port, see [`address`](Self::address).
+    pub fn start(port: u16) -> io::Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+        let address = listener.local_addr()?;
+        let shared = Arc::new(Shared::default());
+
+        let thread = {
+            let shared = shared.clone();
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if shared.shutdown.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    // A client going away mid-request is no reason to stop serving
+                    let _ = stream.and_then(|s| respond(s, &shared));
+                }
+            })
+        };
+
+        Ok(PreviewServer {
+            address,
+            shared,
+            thread: Some(thread),
+        })
+    }
+
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Replaces the served picture. The PNG is encoded here, once, not for every request.
+    pub fn update_image(&self, image: &Image, tone_mapping: &ToneMapping) {
+        let mut png = Vec::new();
+        image.write_png(&mut png, tone_mapping).unwrap();
+        *self.shared.png.lock().unwrap() = Arc::new(png);
+    }
+
+    pub fn update_status(&self, status: PreviewStatus) {
+        *self.shared.status.lock().unwrap() = status;
+    }
+}
+
+impl Drop for PreviewServer {
+    fn drop(&mut self) {
+        self.shared.shutdown.store(true, Ordering::Relaxed);
+        // Wakes the listener up so it sees the flag
+        let _ = TcpStream::connect(self.address);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn respond(mut stream: TcpStream, shared: &Shared) -> io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("");
+    let path = parts.next().unwrap_or("").split('?').next().unwrap_or("");
+
+    match (method, path) {
+        ("GET", "/") => send(&mut stream, "200 OK", "text/html", PAGE.as_bytes()),
+        ("GET", "/image.png") => {
+            let png = shared.png.lock().unwrap().clone();
+            if png.is_empty() {
+                send(
+                    &mut stream,
+                    "503 Service Unavailable",
+                    "text/plain",
+                    b"no image yet",
+                )
+            } else {
+                send(&mut stream, "200 OK", "image/png", &png)
+            }
+        }
+        ("GET", "/status.json") => {
+            let json = shared.status.lock().unwrap().to_json();
+            send(&mut stream, "200 OK", "application/json", json.as_bytes())
+        }
+        ("GET", _) => send(&mut stream, "404 Not Found", "text/plain", b"not found"),
+        _ => send(
+            &mut stream,
+            "405 Method Not Allowed",
+            "text/plain",
+            b"only GET",
+        ),
+    }
+}
+
+fn send(stream: &mut TcpStream, status: &str, content_type: &str, body: &[u8]) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    )?;
+    stream.write_all(body)?;
+    stream.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn get(address: SocketAddr, path: &str) -> String {
+        let mut stream = TcpStream::connect(address).unwrap();
+        write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).unwrap();
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).unwrap();
+        String::from_utf8_lossy(&response).into_owned()
+    }
+
+    #[test]
+    fn serves_the_latest_image_and_status() {
+        let server = PreviewServer::start(0).unwrap();
+        assert!(get(server.address(), "/image.png").starts_with("HTTP/1.1 503"));
+
+        server.update_image(&Image::new(4, 3), &ToneMapping::default());
+        server.update_status(PreviewStatus {
+            passes: 2,
+            samples_per_pixel: 8,
+            eta: None,
+            samples_per_second: 1000.0,
+            rays_per_second: Some(2500.0),
+        });
+
+        let image = get(server.address(), "/image.png?2");
+        assert!(image.starts_with("HTTP/1.1 200 OK"));
+        assert!(image.contains("Content-Type: image/png"));
+        assert!(image.contains("PNG"));
+
+        let status = get(server.address(), "/status.json");
+        assert!(status.ends_with(concat!(
+            r#"{"passes":2,"samples_per_pixel":8,"eta_seconds":null,"#,
+            r#""samples_per_second":1000,"rays_per_second":2500}"#
+        )));
+        assert!(get(server.address(), "/nope").starts_with("HTTP/1.1 404"));
+    }
+
+    #[test]
+    fn rates_that_are_not_numbers_are_null() {
+        let status = PreviewStatus {
+            eta: Some(Duration::from_millis(1500)),
+            samples_per_second: f64::NAN,
+            rays_per_second: Some(f64::INFINITY),
+            ..PreviewStatus::default()
+        };
+        assert_eq!(
+            status.to_json(),
+            concat!(
+                r#"{"passes":0,"samples_per_pixel":0,"eta_seconds":1.5,"#,
+                r#""samples_per_second":null,"rays_per_second":null}"#
+            )
+        );
+    }
+}
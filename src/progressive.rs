@@ -0,0 +1,441 @@
+use crate::adaptive::PixelEstimate;
+use crate::camera::Camera;
+use crate::environment::environment::Environment;
+use crate::film::Film;
+use crate::hit::Hit;
+use crate::image::Image;
+use crate::render::{render_pixels, RenderSettings};
+use crate::scheduler::{CancellationToken, Progress};
+use crate::vector3::Vector3;
+use std::io::{self, BufRead, Write};
+use std::time::{Duration, Instant};
+
+/// When [`ProgressiveRender::render_until`] stops adding passes. It stops at whichever limit is
+/// reached first, and after a single pass if none is set.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct RenderBudget {
+    /// Wall-clock time to spend. A pass still running when it runs out is dropped, unless it is
+    /// the first one.
+    pub time_limit: Option<Duration>,
+    /// Stop once the mean relative error of the pixels falls to this, e.g. `0.01` for 1%.
+    pub target_error: Option<f64>,
+    /// Stop once this many samples per pixel have been taken. Defaults to
+    /// [`DEFAULT_MAX_SAMPLES_PER_PIXEL`], so a target error that is never reached still ends.
+    pub max_samples_per_pixel: Option<usize>,
+}
+
+/// The sample cap of a [`RenderBudget`] that does not set one.
+pub const DEFAULT_MAX_SAMPLES_PER_PIXEL: usize = 1 << 14;
+
+/// What a budgeted render achieved.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BudgetReport {
+    pub samples_per_pixel: usize,
+    pub mean_relative_error: f64,
+    pub elapsed: Duration,
+}
+
+const CHECKPOINT_MAGIC: &str = "RTCHECKPOINT 2";
+
+/// Accumulates passes of `samples_per_pixel` samples each into one image, so a render can be
+/// stopped after any pass, saved to a checkpoint and resumed later. Every pass continues the
+/// sample sequence of the pixels, so a resumed render matches one that was never interrupted.
+///
+/// Adaptive sampling and denoising are left out of the passes.
+#[derive(Debug, Clone)]
+pub struct ProgressiveRender {
+    settings: RenderSettings,
+    film: Film,
+    estimates: Vec<PixelEstimate>,
+    passes: usize,
+}
+
+impl ProgressiveRender {
+    pub fn new(settings: &RenderSettings) -> Self {
+        let mut settings = settings.clone();
+        settings.adaptive = None;
+        settings.denoise = None;
+        let region = settings.region();
+        let pixels = region.width * region.height;
+
+        ProgressiveRender {
+            film: Film::new(region.x, region.y, region.width, region.height),
+            estimates: (0..pixels).map(|_| PixelEstimate::new()).collect(),
+            passes: 0,
+            settings,
+        }
+    }
+
+    pub fn settings(&self) -> &RenderSettings {
+        &self.settings
+    }
+    pub fn passes(&self) -> usize {
+        self.passes
+    }
+    pub fn samples_per_pixel(&self) -> usize {
+        self.passes * self.settings.samples_per_pixel
+    }
+
+    pub fn render_pass(
+        &mut self,
+        world: &dyn Hit,
+        camera: &Camera,
+        environment: &dyn Environment,
+    ) -> &mut Self {
+        self.render_pass_with_progress(
+            world,
+            camera,
+            environment,
+            &|_| {},
+            &CancellationToken::new(),
+        );
+        self
+    }
+
+    /// Like [`render_pass`](Self::render_pass), reporting progress on the tiles of the pass.
+    /// Returns `false`, leaving the accumulated image as it was, if `cancel` was tripped before
+    /// the pass was done.
+    pub fn render_pass_with_progress(
+        &mut self,
+        world: &dyn Hit,
+        camera: &Camera,
+        environment: &dyn Environment,
+        progress: &(dyn Fn(&Progress) + Sync),
+        cancel: &CancellationToken,
+    ) -> bool {
+        let result = render_pixels(
+            world,
+            camera,
+            environment,
+            &self.settings,
+            false,
+            self.samples_per_pixel(),
+            progress,
+            cancel,
+        );
+
+        match result {
+            Some(result) => {
+                self.film.merge(&result.film);
+                for (estimate, pass) in self.estimates.iter_mut().zip(&result.estimates) {
+                    estimate.merge(pass);
+                }
+                self.passes += 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Renders passes until `budget` is met. Passes are the unit of progress, so with a time
+    /// limit `samples_per_pixel` is best kept small.
+    pub fn render_until(
+        &mut self,
+        world: &dyn Hit,
+        camera: &Camera,
+        environment: &dyn Environment,
+        budget: &RenderBudget,
+    ) -> BudgetReport {
+        let started = Instant::now();
+        let out_of_time = || {
+            budget
+                .time_limit
+                .is_some_and(|limit| started.elapsed() >= limit)
+        };
+        let accurate_enough = |render: &Self| {
+            budget
+                .target_error
+                .is_some_and(|target| render.mean_relative_error() <= target)
+        };
+
+        loop {
+            let cancel = CancellationToken::new();
+            let can_stop = self.passes > 0;
+            let finished = self.render_pass_with_progress(
+                world,
+                camera,
+                environment,
+                &|_| {
+                    if can_stop && out_of_time() {
+                        cancel.cancel();
+                    }
+                },
+                &cancel,
+            );
+
+            let unlimited = budget.time_limit.is_none()
+                && budget.target_error.is_none()
+                && budget.max_samples_per_pixel.is_none();
+            let max_samples = budget
+                .max_samples_per_pixel
+                .unwrap_or(DEFAULT_MAX_SAMPLES_PER_PIXEL);
+            if !finished
+                || unlimited
+                || out_of_time()
+                || accurate_enough(self)
+                || self.samples_per_pixel() >= max_samples
+            {
+                break;
+            }
+        }
+
+        BudgetReport {
+            samples_per_pixel: self.samples_per_pixel(),
+            mean_relative_error: self.mean_relative_error(),
+            elapsed: started.elapsed(),
+        }
+    }
+
+    /// The standard error of each pixel's luminance relative to the luminance, averaged over the
+    /// image. Infinite until every pixel has two samples.
+    pub fn mean_relative_error(&self) -> f64 {
+        let total: f64 = self.estimates.iter().map(|e| e.relative_error()).sum();
+        total / self.estimates.len().max(1) as f64
+    }
+
+    /// Everything accumulated so far.
+    pub fn image(&self) -> Image {
+        Image::from_pixels(self.film.width(), self.film.height(), self.film.colors())
+    }
+
+    /// Saves the running sums. The settings are not saved, only checked when resuming.
+    pub fn write_checkpoint<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(out, "{}", CHECKPOINT_MAGIC)?;
+        let region = self.settings.region();
+        writeln!(
+            out,
+            "{} {} {} {} {} {} {} {} {}",
+            self.settings.image_width,
+            self.settings.image_height,
+            region.x,
+            region.y,
+            region.width,
+            region.height,
+            self.settings.samples_per_pixel,
+            self.settings.seed,
+            self.passes
+        )?;
+        writeln!(out, "{}", sampling_settings(&self.settings))?;
+
+        let (sums, weights) = self.film.sums();
+        for ((sum, weight), estimate) in sums.iter().zip(weights).zip(&self.estimates) {
+            let (n, mean, m2) = estimate.parts();
+            for v in [sum.x(), sum.y(), sum.z(), *weight, n as f64, mean, m2] {
+                out.write_all(&v.to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Picks up a render saved by [`write_checkpoint`](Self::write_checkpoint). `settings` must
+    /// match the ones it was started with.
+    pub fn resume<R: BufRead>(settings: &RenderSettings, input: &mut R) -> io::Result<Self> {
+        let mut render = Self::new(settings);
+
+        let mut line = String::new();
+        input.read_line(&mut line)?;
+        if line.trim_end() != CHECKPOINT_MAGIC {
+            return Err(invalid_data("not a render checkpoint"));
+        }
+
+        line.clear();
+        input.read_line(&mut line)?;
+        let header = line
+            .split_whitespace()
+            .map(|s| s.parse::<u64>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| invalid_data("malformed checkpoint header"))?;
+        let region = settings.region();
+        let expected = [
+            settings.image_width as u64,
+            settings.image_height as u64,
+            region.x as u64,
+            region.y as u64,
+            region.width as u64,
+            region.height as u64,
+            settings.samples_per_pixel as u64,
+            settings.seed,
+        ];
+        line.clear();
+        input.read_line(&mut line)?;
+        match header.split_last() {
+            Some((passes, rest))
+                if rest == expected && line.trim_end() == sampling_settings(settings) =>
+            {
+                render.passes = *passes as usize
+            }
+            _ => return Err(invalid_data("checkpoint was made with different settings")),
+        }
+
+        let (sums, weights) = render.film.sums_mut();
+        for ((sum, weight), estimate) in sums
+            .iter_mut()
+            .zip(weights.iter_mut())
+            .zip(render.estimates.iter_mut())
+        {
+            let mut values = [0.0; 7];
+            for v in values.iter_mut() {
+                let mut bytes = [0; 8];
+                input.read_exact(&mut bytes)?;
+                *v = f64::from_le_bytes(bytes);
+            }
+            *sum = Vector3::new(values[0], values[1], values[2]);
+            *weight = values[3];
+            *estimate = PixelEstimate::from_parts((values[4] as usize, values[5], values[6]));
+        }
+
+        Ok(render)
+    }
+}
+
+/// Renders progressively from scratch until `budget` is met. `settings.samples_per_pixel` is the
+/// number of samples added per pass.
+pub fn render_with_budget(
+    world: &dyn Hit,
+    camera: &Camera,
+    environment: &dyn Environment,
+    settings: &RenderSettings,
+    budget: &RenderBudget,
+) -> (Image, BudgetReport) {
+    let mut render = ProgressiveRender::new(settings);
+    let report = render.render_until(world, camera, environment, budget);
+    (render.image(), report)
+}
+
+/// The settings the running sums depend on besides the image size, region, sample count and
+/// seed, in a form that compares exactly. Adaptive sampling is left out of the passes, so it is
+/// not among them.
+fn sampling_settings(settings: &RenderSettings) -> String {
+    format!(
+        "{:?} {:?} {}",
+        settings.filter, settings.sampler, settings.max_depth
+    )
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adaptive::AdaptiveSampling;
+    use crate::environment::gradient_sky::GradientSky;
+    use crate::filter::Filter;
+    use crate::hit_objects::{HitObject, HitObjects};
+    use crate::material::lambertian::Lambertian;
+    use crate::sampler::sampler::SamplerKind;
+    use crate::sphere::Sphere;
+    use crate::vector3::{Color, Point3};
+    use std::sync::Arc;
+
+    fn world() -> HitObjects {
+        let mut world = HitObjects::new();
+        world.add(HitObject::Sphere(Sphere::new(
+            Point3::new_z(-1.0),
+            0.5,
+            Arc::new(Lambertian::new(Color::red())),
+        )));
+        world
+    }
+
+    #[test]
+    fn resumed_renders_match_uninterrupted_ones() {
+        let world = world();
+        let camera = Camera::default();
+        let sky = GradientSky::default();
+        let mut settings = RenderSettings::new(8, 6);
+        settings.samples_per_pixel = 2;
+
+        let mut uninterrupted = ProgressiveRender::new(&settings);
+        uninterrupted
+            .render_pass(&world, &camera, &sky)
+            .render_pass(&world, &camera, &sky);
+
+        let mut first = ProgressiveRender::new(&settings);
+        first.render_pass(&world, &camera, &sky);
+        let mut checkpoint = Vec::new();
+        first.write_checkpoint(&mut checkpoint).unwrap();
+        let mut resumed = ProgressiveRender::resume(&settings, &mut &checkpoint[..]).unwrap();
+        resumed.render_pass(&world, &camera, &sky);
+
+        assert_eq!(resumed.samples_per_pixel(), 4);
+        assert_eq!(resumed.image(), uninterrupted.image());
+        assert_ne!(first.image(), uninterrupted.image());
+
+        let mut changes = vec![settings.clone(); 4];
+        changes[0].seed = 1;
+        changes[1].filter = Filter::Tent { radius: 1.0 };
+        changes[2].sampler = SamplerKind::Halton;
+        changes[3].max_depth = 10;
+        for changed in &changes {
+            assert!(ProgressiveRender::resume(changed, &mut &checkpoint[..]).is_err());
+        }
+    }
+
+    #[test]
+    fn resuming_ignores_adaptive_sampling() {
+        let world = world();
+        let camera = Camera::default();
+        let sky = GradientSky::default();
+        let mut settings = RenderSettings::new(8, 6);
+        settings.samples_per_pixel = 2;
+
+        let mut uninterrupted = ProgressiveRender::new(&settings);
+        uninterrupted
+            .render_pass(&world, &camera, &sky)
+            .render_pass(&world, &camera, &sky);
+
+        let mut first = ProgressiveRender::new(&settings);
+        first.render_pass(&world, &camera, &sky);
+        let mut checkpoint = Vec::new();
+        first.write_checkpoint(&mut checkpoint).unwrap();
+
+        settings.adaptive = Some(AdaptiveSampling::default());
+        let mut resumed = ProgressiveRender::resume(&settings, &mut &checkpoint[..]).unwrap();
+        resumed.render_pass(&world, &camera, &sky);
+        assert_eq!(resumed.image(), uninterrupted.image());
+    }
+
+    #[test]
+    fn budgets_stop_at_the_target_error() {
+        let mut settings = RenderSettings::new(8, 6);
+        settings.samples_per_pixel = 4;
+        let budget = RenderBudget {
+            time_limit: Some(Duration::from_secs(60)),
+            target_error: Some(0.05),
+            ..RenderBudget::default()
+        };
+
+        let (_, report) = render_with_budget(
+            &world(),
+            &Camera::default(),
+            &GradientSky::default(),
+            &settings,
+            &budget,
+        );
+
+        assert!(report.mean_relative_error <= 0.05);
+        assert!(report.samples_per_pixel > 4);
+        assert!(report.elapsed < Duration::from_secs(60));
+    }
+
+    #[test]
+    fn unreachable_target_errors_stop_at_the_sample_cap() {
+        let mut settings = RenderSettings::new(4, 3);
+        settings.samples_per_pixel = 256;
+        let camera = Camera::default();
+        let sky = GradientSky::default();
+
+        let mut budget = RenderBudget {
+            target_error: Some(0.0),
+            ..RenderBudget::default()
+        };
+        let (_, report) = render_with_budget(&world(), &camera, &sky, &settings, &budget);
+        assert_eq!(report.samples_per_pixel, DEFAULT_MAX_SAMPLES_PER_PIXEL);
+
+        budget.max_samples_per_pixel = Some(1000);
+        let (_, report) = render_with_budget(&world(), &camera, &sky, &settings, &budget);
+        assert_eq!(report.samples_per_pixel, 1024);
+    }
+}
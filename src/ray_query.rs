@@ -0,0 +1,118 @@
+use crate::hit::Hit;
+use crate::ray::Ray;
+use crate::vector3::Vector3;
+use rayon::prelude::*;
+use std::sync::Arc;
+
+/// A ray and the part of it to test, from `t_min` to `t_max`.
+#[derive(Debug, Clone)]
+pub struct RayQuery {
+    pub ray: Ray,
+    pub t_min: f64,
+    pub t_max: f64,
+}
+
+impl RayQuery {
+    pub fn new(ray: Ray, t_min: f64, t_max: f64) -> Self {
+        Self { ray, t_min, t_max }
+    }
+}
+
+/// The nearest surface a [`RayQuery`] hits.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RayHit {
+    /// Ray parameter of the hit, the distance to it when the direction is a unit vector.
+    pub t: f64,
+    /// Unit normal facing against the ray.
+    pub normal: Vector3,
+    /// Whether the ray hit the outside of the surface.
+    pub front_face: bool,
+    /// Index into the [`HitObjects`](crate::hit_objects::HitObjects) the world, or the BVH it
+    /// was built from, holds. `None` if the world is a lone object.
+    pub object_index: Option<usize>,
+    /// The same for every object sharing one `Arc` of a material, and different for every other
+    /// material alive at the same time. Not stable across runs.
+    pub material_id: usize,
+}
+
+/// Casts every query against `world` in parallel and returns the nearest hit of each, in the
+/// order of `queries`.
+pub fn cast_rays(world: &dyn Hit, queries: &[RayQuery]) -> Vec<Option<RayHit>> {
+    queries
+        .par_iter()
+        .map(|query| {
+            world
+                .hit(&query.ray, query.t_min, query.t_max)
+                .map(|record| RayHit {
+                    t: record.t(),
+                    normal: record.normal().clone(),
+                    front_face: record.front_face(),
+                    object_index: record.object_index(),
+                    material_id: Arc::as_ptr(record.material()) as *const () as usize,
+                })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bvh::node::Node;
+    use crate::hit_objects::{HitObject, HitObjects};
+    use crate::material::lambertian::Lambertian;
+    use crate::material::material::Material;
+    use crate::sphere::Sphere;
+    use crate::vector3::{Color, Point3};
+    use rand::thread_rng;
+
+    #[test]
+    fn finds_the_nearest_hit_of_every_ray() {
+        let shared: Arc<dyn Material> = Arc::new(Lambertian::new(Color::red()));
+        let mut world = HitObjects::new();
+        for (x, material) in [
+            (0.0, shared.clone()),
+            (
+                3.0,
+                Arc::new(Lambertian::new(Color::red())) as Arc<dyn Material>,
+            ),
+            (6.0, shared),
+        ] {
+            world.add(HitObject::Sphere(Sphere::new(
+                Point3::new(x, 0.0, -5.0),
+                1.0,
+                material,
+            )));
+        }
+        let bvh = Node::new(&mut thread_rng(), &world.0, 0.0, 1.0).unwrap();
+        let down_z = |x| Ray::new(Point3::new_x(x), Vector3::new_z(-1.0), 0.0);
+        let queries = [
+            RayQuery::new(down_z(0.0), 0.0, f64::INFINITY),
+            RayQuery::new(down_z(3.0), 0.0, f64::INFINITY),
+            RayQuery::new(down_z(6.0), 0.0, f64::INFINITY),
+            RayQuery::new(down_z(1.5), 0.0, f64::INFINITY),
+            RayQuery::new(down_z(0.0), 0.0, 3.0),
+            RayQuery::new(down_z(0.0), 5.0, f64::INFINITY),
+        ];
+
+        for world in [&world as &dyn Hit, &bvh] {
+            let outcomes = cast_rays(world, &queries);
+            assert_eq!(outcomes.len(), queries.len());
+
+            let hits: Vec<&RayHit> = outcomes[..3].iter().map(|h| h.as_ref().unwrap()).collect();
+            for (i, hit) in hits.iter().enumerate() {
+                assert_eq!(hit.t, 4.0);
+                assert_eq!(hit.normal, Vector3::new_z(1.0));
+                assert!(hit.front_face);
+                assert_eq!(hit.object_index, Some(i));
+            }
+            assert_eq!(hits[0].material_id, hits[2].material_id);
+            assert_ne!(hits[0].material_id, hits[1].material_id);
+
+            assert!(outcomes[3].is_none());
+            assert!(outcomes[4].is_none());
+            let inside = outcomes[5].as_ref().unwrap();
+            assert_eq!(inside.t, 6.0);
+            assert!(!inside.front_face);
+        }
+    }
+}
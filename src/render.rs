@@ -0,0 +1,734 @@
+use crate::adaptive::{AdaptiveSampling, PixelEstimate, SampleCounts};
+use crate::aov::{AovAccumulator, AovPixel, Aovs};
+use crate::camera::Camera;
+use crate::denoise::{denoise, DenoiseSettings};
+use crate::environment::environment::Environment;
+use crate::film::Film;
+use crate::filter::Filter;
+use crate::hit::{Hit, HitRecord};
+use crate::image::Image;
+use crate::ray::Ray;
+use crate::sampler::sampler::{
+    Sampler, SamplerKind, BSDF_DIMENSION, BSDF_DIMENSIONS_PER_BOUNCE, LENS_DIMENSION,
+    PIXEL_DIMENSION, TIME_DIMENSION,
+};
+use crate::scheduler::{tiles, CancellationToken, Progress, TileOrder};
+use crate::telemetry::{self, Counter};
+use crate::tone_mapping::ToneMapping;
+use crate::vector3::{Color, Vector3};
+use rayon::prelude::*;
+use std::sync::Mutex;
+use std::time::Instant;
+
+#[derive(Debug, Clone)]
+pub struct RenderSettings {
+    pub image_width: usize,
+    pub image_height: usize,
+    /// Ignored when `adaptive` is set.
+    pub samples_per_pixel: usize,
+    pub max_depth: usize,
+    /// Used when the result is written to an 8-bit format.
+    pub tone_mapping: ToneMapping,
+    /// Filters the result guided by AOVs, which are then recorded even by [`render`].
+    pub denoise: Option<DenoiseSettings>,
+    /// Varies the number of samples per pixel with how noisy each pixel is.
+    pub adaptive: Option<AdaptiveSampling>,
+    pub sampler: SamplerKind,
+    /// Reconstructs pixels from the samples around them. AOVs always average the pixel's own
+    /// samples.
+    pub filter: Filter,
+    /// Pixels are rendered in square tiles of this size, one rayon task each.
+    pub tile_size: usize,
+    pub tile_order: TileOrder,
+    /// Renders only this region of the image, framed as it is in the full image. The result is
+    /// the size of the region.
+    pub crop: Option<CropWindow>,
+    /// Renders with the same settings and seed give the same image.
+    pub seed: u64,
+}
+
+impl RenderSettings {
+    pub fn new(image_width: usize, image_height: usize) -> Self {
+        RenderSettings {
+            image_width,
+            image_height,
+            samples_per_pixel: 100,
+            max_depth: 50,
+            tone_mapping: ToneMapping::default(),
+            denoise: None,
+            adaptive: None,
+            sampler: SamplerKind::Independent,
+            filter: Filter::default(),
+            tile_size: 16,
+            tile_order: TileOrder::default(),
+            crop: None,
+            seed: 0,
+        }
+    }
+
+    /// The part of the image that gets rendered: the crop window, or else the whole image.
+    pub fn region(&self) -> CropWindow {
+        self.crop
+            .unwrap_or_else(|| CropWindow::new(0, 0, self.image_width, self.image_height))
+    }
+}
+
+/// A rectangle of pixels, rows counting down from the top of the image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CropWindow {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl CropWindow {
+    pub fn new(x: usize, y: usize, width: usize, height: usize) -> Self {
+        CropWindow {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+}
+
+pub(crate) struct RenderResult {
+    pub(crate) image: Image,
+    pub(crate) aovs: Option<Aovs>,
+    pub(crate) sample_counts: SampleCounts,
+    /// The unfiltered sums behind `image`, before any denoising.
+    pub(crate) film: Film,
+    pub(crate) estimates: Vec<PixelEstimate>,
+}
+
+/// Renders `world` as seen through `camera`.
+pub fn render(
+    world: &dyn Hit,
+    camera: &Camera,
+    environment: &dyn Environment,
+    settings: &RenderSettings,
+) -> Image {
+    let with_aovs = settings.denoise.is_some();
+    render_pixels(
+        world,
+        camera,
+        environment,
+        settings,
+        with_aovs,
+        0,
+        &|_| {},
+        &CancellationToken::new(),
+    )
+    .unwrap()
+    .image
+}
+
+/// Renders like [`render`], calling `progress` from whichever thread finished each tile, without
+/// holding up the other threads, so the reports can arrive out of order. Returns `None` if
+/// `cancel` was tripped before every tile was rendered.
+pub fn render_with_progress(
+    world: &dyn Hit,
+    camera: &Camera,
+    environment: &dyn Environment,
+    settings: &RenderSettings,
+    progress: &(dyn Fn(&Progress) + Sync),
+    cancel: &CancellationToken,
+) -> Option<Image> {
+    let with_aovs = settings.denoise.is_some();
+    render_pixels(
+        world,
+        camera,
+        environment,
+        settings,
+        with_aovs,
+        0,
+        progress,
+        cancel,
+    )
+    .map(|result| result.image)
+}
+
+/// Renders like [`render`] while also recording [`Aovs`] from the first hit of every camera
+/// sample.
+pub fn render_with_aovs(
+    world: &dyn Hit,
+    camera: &Camera,
+    environment: &dyn Environment,
+    settings: &RenderSettings,
+) -> (Image, Aovs) {
+    let result = render_pixels(
+        world,
+        camera,
+        environment,
+        settings,
+        true,
+        0,
+        &|_| {},
+        &CancellationToken::new(),
+    )
+    .unwrap();
+    (result.image, result.aovs.unwrap())
+}
+
+/// Renders like [`render`] and reports how many samples each pixel took, which is mostly of
+/// interest with adaptive sampling.
+pub fn render_with_sample_counts(
+    world: &dyn Hit,
+    camera: &Camera,
+    environment: &dyn Environment,
+    settings: &RenderSettings,
+) -> (Image, SampleCounts) {
+    let with_aovs = settings.denoise.is_some();
+    let result = render_pixels(
+        world,
+        camera,
+        environment,
+        settings,
+        with_aovs,
+        0,
+        &|_| {},
+        &CancellationToken::new(),
+    )
+    .unwrap();
+    (result.image, result.sample_counts)
+}
+
+/// Renders every tile, numbering the samples of each pixel from `first_sample` on. Returns `None`
+/// if cancelled before all tiles were done.
+///
+/// Pixels within the filter radius around a crop window are sampled too, so the pixels of the
+/// window come out as they would in the full image.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn render_pixels(
+    world: &dyn Hit,
+    camera: &Camera,
+    environment: &dyn Environment,
+    settings: &RenderSettings,
+    with_aovs: bool,
+    first_sample: usize,
+    progress: &(dyn Fn(&Progress) + Sync),
+    cancel: &CancellationToken,
+) -> Option<RenderResult> {
+    telemetry::phase("render", || {
+        render_tiles(
+            world,
+            camera,
+            environment,
+            settings,
+            with_aovs,
+            first_sample,
+            progress,
+            cancel,
+        )
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+fn render_tiles(
+    world: &dyn Hit,
+    camera: &Camera,
+    environment: &dyn Environment,
+    settings: &RenderSettings,
+    with_aovs: bool,
+    first_sample: usize,
+    progress: &(dyn Fn(&Progress) + Sync),
+    cancel: &CancellationToken,
+) -> Option<RenderResult> {
+    let started = Instant::now();
+    let width = settings.image_width;
+    let height = settings.image_height;
+    let margin = settings.filter.radius().ceil() as usize;
+    let region = settings.region();
+    assert!(
+        region.x + region.width <= width && region.y + region.height <= height,
+        "crop window {:?} lies outside the image",
+        region
+    );
+
+    let left = region.x.saturating_sub(margin);
+    let top = region.y.saturating_sub(margin);
+    let right = (region.x + region.width + margin).min(width);
+    let bottom = (region.y + region.height + margin).min(height);
+    let tiles = tiles(
+        right - left,
+        bottom - top,
+        settings.tile_size,
+        settings.tile_order,
+    );
+    let total_tiles = tiles.len();
+
+    let pixels = region.width * region.height;
+    let state = Mutex::new(TileState {
+        film: Film::new(region.x, region.y, region.width, region.height),
+        aov_pixels: (0..pixels).map(|_| None).collect(),
+        estimates: (0..pixels).map(|_| PixelEstimate::new()).collect(),
+        completed_tiles: 0,
+    });
+
+    tiles.into_iter().par_bridge().for_each(|mut tile| {
+        tile.x += left;
+        tile.y += top;
+        if cancel.is_cancelled() {
+            return;
+        }
+
+        let mut sampler = settings.sampler.build(max_samples(settings), settings.seed);
+        let left = tile.x.saturating_sub(margin);
+        let top = tile.y.saturating_sub(margin);
+        let mut film = Film::new(
+            left,
+            top,
+            (tile.x + tile.width + margin).min(width) - left,
+            (tile.y + tile.height + margin).min(height) - top,
+        );
+
+        let pixels: Vec<_> = (tile.y..tile.y + tile.height)
+            .flat_map(|row| (tile.x..tile.x + tile.width).map(move |i| (i, row)))
+            .map(|pixel| {
+                let result = render_pixel(
+                    sampler.as_mut(),
+                    &mut film,
+                    world,
+                    camera,
+                    environment,
+                    settings,
+                    with_aovs,
+                    first_sample,
+                    pixel,
+                );
+                (pixel, result)
+            })
+            .collect();
+
+        telemetry::flush();
+        let mut state = state.lock().unwrap();
+        state.film.merge(&film);
+        let inside = |&((i, row), _): &_| {
+            (region.x..region.x + region.width).contains(&i)
+                && (region.y..region.y + region.height).contains(&row)
+        };
+        for ((i, row), (aov, estimate)) in pixels.into_iter().filter(inside) {
+            let index = (row - region.y) * region.width + i - region.x;
+            state.aov_pixels[index] = aov;
+            state.estimates[index] = estimate;
+        }
+        state.completed_tiles += 1;
+        let completed_tiles = state.completed_tiles;
+        // Released first, so a slow callback does not hold up the other tiles
+        drop(state);
+        progress(&Progress::new(
+            completed_tiles,
+            total_tiles,
+            started.elapsed(),
+        ));
+    });
+
+    let TileState {
+        film,
+        aov_pixels,
+        estimates,
+        completed_tiles,
+    } = state.into_inner().unwrap();
+    if completed_tiles < total_tiles {
+        return None;
+    }
+
+    let aovs = with_aovs.then(|| {
+        let pixels = aov_pixels.into_iter().map(Option::unwrap).collect();
+        Aovs::from_pixels(region.width, region.height, pixels)
+    });
+    let image = Image::from_pixels(region.width, region.height, film.colors());
+    let image = match (&settings.denoise, &aovs) {
+        (Some(denoise_settings), Some(guides)) => denoise(&image, guides, denoise_settings),
+        _ => image,
+    };
+
+    Some(RenderResult {
+        image,
+        aovs,
+        sample_counts: SampleCounts::new(
+            region.width,
+            region.height,
+            estimates.iter().map(|e| e.count()).collect(),
+        ),
+        film,
+        estimates,
+    })
+}
+
+/// What the tiles finished so far have added up to.
+struct TileState {
+    film: Film,
+    aov_pixels: Vec<Option<AovPixel>>,
+    estimates: Vec<PixelEstimate>,
+    completed_tiles: usize,
+}
+
+pub(crate) fn max_samples(settings: &RenderSettings) -> usize {
+    match &settings.adaptive {
+        Some(a) => a.max_samples,
+        None => settings.samples_per_pixel,
+    }
+}
+
+/// How many samples a pixel takes first, and how many more each time it has not converged.
+pub(crate) fn sample_batches(settings: &RenderSettings) -> (usize, usize) {
+    match &settings.adaptive {
+        Some(a) => (a.min_samples, a.batch_size.max(1)),
+        None => (settings.samples_per_pixel, settings.samples_per_pixel),
+    }
+}
+
+/// Takes every sample of the pixel at column `i` and `row` from the top, splatting them into
+/// `film`. Returns the pixel's AOVs, if recorded, and the estimate of its noise.
+#[allow(clippy::too_many_arguments)]
+fn render_pixel(
+    sampler: &mut dyn Sampler,
+    film: &mut Film,
+    world: &dyn Hit,
+    camera: &Camera,
+    environment: &dyn Environment,
+    settings: &RenderSettings,
+    with_aovs: bool,
+    first_sample: usize,
+    (i, row): (usize, usize),
+) -> (Option<AovPixel>, PixelEstimate) {
+    let width = settings.image_width;
+    let height = settings.image_height;
+    let j = height - 1 - row;
+    let max_samples = max_samples(settings);
+    let (first_batch, batch_size) = sample_batches(settings);
+
+    let mut aovs = with_aovs.then(AovAccumulator::new);
+    let mut estimate = PixelEstimate::new();
+    let mut samples = 0;
+    let mut batch = first_batch;
+
+    loop {
+        for index in first_sample + samples..first_sample + samples + batch {
+            let (r, position) = camera_ray(
+                sampler,
+                camera,
+                (i, row),
+                index,
+                (i as f64, j as f64),
+                (width, height),
+            );
+
+            telemetry::count(Counter::PrimaryRay);
+            let rec = world.hit(&r, 0.001, f64::INFINITY);
+            if let Some(aovs) = aovs.as_mut() {
+                aovs.add(&r, rec.as_ref(), environment);
+            }
+
+            // As in trace, a path with no depth left carries no light
+            let color = if settings.max_depth == 0 {
+                telemetry::count_path(0);
+                Vector3::zero()
+            } else {
+                Vector3::from(shade(
+                    sampler,
+                    &r,
+                    rec.as_ref(),
+                    world,
+                    environment,
+                    settings.max_depth,
+                    0,
+                ))
+            };
+            estimate.add(&color);
+            film.splat(&settings.filter, position, &color);
+        }
+        samples += batch;
+
+        let converged = settings
+            .adaptive
+            .map(|a| estimate.relative_error() <= a.threshold)
+            .unwrap_or(true);
+        if converged || samples >= max_samples {
+            break;
+        }
+        batch = batch_size.min(max_samples - samples);
+    }
+
+    (aovs.map(|a| a.finish()), estimate)
+}
+
+/// Starts `sample_index` of the pixel at column `pixel.0` and row `pixel.1` from the top, and
+/// builds its camera ray. `position` is the pixel's lower left corner in the book's coordinates,
+/// where rows count up from the bottom. Also returns where the sample lies on the film, in pixels
+/// from the top left corner.
+pub(crate) fn camera_ray(
+    sampler: &mut dyn Sampler,
+    camera: &Camera,
+    pixel: (usize, usize),
+    sample_index: usize,
+    position: (f64, f64),
+    (width, height): (usize, usize),
+) -> (Ray, (f64, f64)) {
+    sampler.start_sample(pixel, sample_index);
+    sampler.set_dimension(PIXEL_DIMENSION);
+    let (du, dv) = sampler.get_2d();
+    sampler.set_dimension(LENS_DIMENSION);
+    let lens = sampler.get_2d();
+    sampler.set_dimension(TIME_DIMENSION);
+    let time = sampler.get_1d();
+
+    // A single column or row spans the whole frame rather than dividing by zero
+    let u = (position.0 + du) / (width - 1).max(1) as f64;
+    let v = (position.1 + dv) / (height - 1).max(1) as f64;
+    let film_position = (pixel.0 as f64 + du, pixel.1 as f64 + 1.0 - dv);
+    (camera.ray_from_sample(u, v, lens, time), film_position)
+}
+
+/// The ray through the center of the pixel at column `i` and `row` from the top, from the middle
+/// of the lens at the middle of the shutter interval.
+pub(crate) fn center_ray(
+    camera: &Camera,
+    (width, height): (usize, usize),
+    (i, row): (usize, usize),
+) -> Ray {
+    let j = height - 1 - row;
+    let u = (i as f64 + 0.5) / (width - 1).max(1) as f64;
+    let v = (j as f64 + 0.5) / (height - 1).max(1) as f64;
+    camera.ray_from_sample(u, v, (0.5, 0.5), 0.5)
+}
+
+pub fn ray_color(
+    sampler: &mut dyn Sampler,
+    ray: &Ray,
+    world: &dyn Hit,
+    environment: &dyn Environment,
+    depth: usize,
+) -> Color {
+    trace(sampler, ray, world, environment, depth, 0)
+}
+
+fn trace(
+    sampler: &mut dyn Sampler,
+    ray: &Ray,
+    world: &dyn Hit,
+    environment: &dyn Environment,
+    depth: usize,
+    bounce: usize,
+) -> Color {
+    if depth == 0 {
+        telemetry::count_path(bounce);
+        return Color::black();
+    }
+    if bounce > 0 {
+        telemetry::count(Counter::SecondaryRay);
+    }
+
+    let rec = world.hit(ray, 0.001, f64::INFINITY);
+    shade(
+        sampler,
+        ray,
+        rec.as_ref(),
+        world,
+        environment,
+        depth,
+        bounce,
+    )
+}
+
+/// The color carried back along `ray`, given what it has already been found to hit. `bounce`
+/// counts the scattering events before this one and picks the sampler dimensions used for it.
+fn shade(
+    sampler: &mut dyn Sampler,
+    ray: &Ray,
+    record: Option<&HitRecord>,
+    world: &dyn Hit,
+    environment: &dyn Environment,
+    depth: usize,
+    bounce: usize,
+) -> Color {
+    match record {
+        Some(r) => {
+            sampler.set_dimension(BSDF_DIMENSION + bounce * BSDF_DIMENSIONS_PER_BOUNCE);
+            let emitted = Vector3::from(r.material().emitted(r));
+            let scattered = r.material().scatter(sampler, ray, r);
+            if scattered.is_none() {
+                telemetry::count_path(bounce + 1);
+            }
+            Color::from(
+                emitted
+                    + scattered
+                        .map(|result| {
+                            Vector3::from(result.attenuation).hadamard_product(&Vector3::from(
+                                trace(
+                                    sampler,
+                                    &result.scattered,
+                                    world,
+                                    environment,
+                                    depth - 1,
+                                    bounce + 1,
+                                ),
+                            ))
+                        })
+                        .unwrap_or_else(Vector3::zero),
+            )
+        }
+        None => {
+            telemetry::count_path(bounce);
+            environment.value(ray.direction())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::environment::gradient_sky::GradientSky;
+    use crate::hit_objects::{HitObject, HitObjects};
+    use crate::material::lambertian::Lambertian;
+    use crate::sphere::Sphere;
+    use crate::vector3::Point3;
+    use std::sync::Arc;
+
+    #[test]
+    fn single_columns_and_rows_render_finite_colors() {
+        let world = HitObjects::new_one(HitObject::Sphere(Sphere::new(
+            Point3::new_z(-1.0),
+            0.5,
+            Arc::new(Lambertian::new(Color::red())),
+        )));
+        let camera = Camera::default();
+        let sky = GradientSky::default();
+
+        for size in [(1, 4), (4, 1), (1, 1)] {
+            let mut settings = RenderSettings::new(size.0, size.1);
+            settings.samples_per_pixel = 4;
+            let image = render(&world, &camera, &sky, &settings);
+            for pixel in image.pixels() {
+                assert!(pixel.r().is_finite() && pixel.g().is_finite(), "{:?}", size);
+            }
+            let ray = center_ray(&camera, size, (0, 0));
+            assert!(ray.direction().x().is_finite() && ray.direction().y().is_finite());
+        }
+    }
+
+    #[test]
+    fn aovs_describe_the_first_hit() {
+        let mut world = HitObjects::new();
+        world.add(HitObject::Sphere(Sphere::new(
+            Point3::new_z(-2.0),
+            1.5,
+            Arc::new(Lambertian::new(Color::red())),
+        )));
+        let camera = Camera::new(
+            Point3::zero(),
+            Point3::new_z(-1.0),
+            Vector3::new_y(1.0),
+            90.0,
+            1.0,
+            0.0,
+            1.0,
+            0.0,
+            1.0,
+        );
+        let mut settings = RenderSettings::new(5, 5);
+        settings.samples_per_pixel = 4;
+
+        let (_, aovs) = render_with_aovs(&world, &camera, &GradientSky::default(), &settings);
+
+        assert_eq!(aovs.object_id.pixel(2, 2), &Color::new_all(0.0));
+        assert_eq!(aovs.object_id.pixel(0, 0), &Color::new_all(-1.0));
+        assert!(aovs.normal.pixel(2, 2).b() > 0.5);
+        assert!((0.5..1.0).contains(&aovs.depth.pixel(2, 2).r()));
+        assert!(aovs.depth.pixel(0, 0).r().is_infinite());
+        assert_eq!(aovs.albedo.pixel(2, 2), &Color::red());
+    }
+
+    #[test]
+    fn progress_counts_tiles_and_cancellation_stops_the_render() {
+        let camera = Camera::default();
+        let mut settings = RenderSettings::new(20, 10);
+        settings.samples_per_pixel = 1;
+        settings.tile_size = 8;
+        let sky = GradientSky::default();
+
+        let reports = Mutex::new(Vec::new());
+        let image = render_with_progress(
+            &HitObjects::new(),
+            &camera,
+            &sky,
+            &settings,
+            &|p| reports.lock().unwrap().push(p.completed_tiles),
+            &CancellationToken::new(),
+        );
+        let mut reports = reports.into_inner().unwrap();
+        reports.sort_unstable();
+        assert!(image.is_some());
+        assert_eq!(reports, (1..=6).collect::<Vec<_>>());
+
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        let image = render_with_progress(
+            &HitObjects::new(),
+            &camera,
+            &sky,
+            &settings,
+            &|_| panic!("no tile should be rendered"),
+            &cancel,
+        );
+        assert!(image.is_none());
+    }
+
+    #[test]
+    fn crop_windows_match_the_full_frame() {
+        let mut world = HitObjects::new();
+        world.add(HitObject::Sphere(Sphere::new(
+            Point3::new_z(-1.0),
+            0.5,
+            Arc::new(Lambertian::new(Color::red())),
+        )));
+        let camera = Camera::default();
+        let sky = GradientSky::default();
+        let mut settings = RenderSettings::new(16, 9);
+        settings.samples_per_pixel = 2;
+
+        let full = render(&world, &camera, &sky, &settings);
+        settings.crop = Some(CropWindow::new(5, 2, 6, 4));
+        let crop = render(&world, &camera, &sky, &settings);
+
+        assert_eq!((crop.width(), crop.height()), (6, 4));
+        for y in 0..4 {
+            for x in 0..6 {
+                assert_eq!(crop.pixel(x, y), full.pixel(x + 5, y + 2));
+            }
+        }
+
+        let mut pasted = full.clone();
+        pasted.paste(&Image::new(6, 4), 5, 2);
+        assert_ne!(pasted, full);
+        pasted.paste(&crop, 5, 2);
+        assert_eq!(pasted, full);
+    }
+
+    #[test]
+    fn wide_filters_cover_the_image_edges() {
+        let camera = Camera::new(
+            Point3::zero(),
+            Point3::new_z(-1.0),
+            Vector3::new_y(1.0),
+            90.0,
+            1.0,
+            0.0,
+            1.0,
+            0.0,
+            1.0,
+        );
+        let mut settings = RenderSettings::new(6, 4);
+        settings.samples_per_pixel = 4;
+        settings.filter = Filter::Lanczos { radius: 3.0 };
+
+        let sky = GradientSky::new(Color::new_all(0.5), Color::new_all(0.5));
+        let image = render(&HitObjects::new(), &camera, &sky, &settings);
+
+        for pixel in image.pixels() {
+            assert!((pixel.r() - 0.5).abs() < 1e-9, "{:?}", pixel);
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use crate::sampler::sampler::{to_unit, SampleState, Sampler};
+
+const PRIMES: [u64; 64] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131, 137, 139, 149, 151, 157, 163, 167, 173, 179, 181, 191, 193,
+    197, 199, 211, 223, 227, 229, 233, 239, 241, 251, 257, 263, 269, 271, 277, 281, 283, 293, 307,
+    311,
+];
+
+/// The Halton sequence, with the radical inverse in the n-th prime base for dimension n, shifted
+/// by a random offset per pixel and dimension (a Cranley-Patterson rotation). Dimensions past
+/// the prime table get independent random values.
+#[derive(Debug, Clone)]
+pub struct HaltonSampler {
+    state: SampleState,
+}
+
+impl HaltonSampler {
+    pub fn new(seed: u64) -> Self {
+        HaltonSampler {
+            state: SampleState::new(seed),
+        }
+    }
+}
+
+impl Sampler for HaltonSampler {
+    fn start_sample(&mut self, pixel: (usize, usize), sample_index: usize) {
+        self.state.start_sample(pixel, sample_index);
+    }
+
+    fn set_dimension(&mut self, dimension: usize) {
+        self.state.dimension = dimension;
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let dimension = self.state.dimension;
+        self.state.dimension += 1;
+
+        match PRIMES.get(dimension) {
+            Some(base) => {
+                let offset = to_unit(self.state.pixel_hash(dimension));
+                (radical_inverse(*base, self.state.sample_index as u64) + offset).fract()
+            }
+            None => self.state.random(dimension),
+        }
+    }
+}
+
+pub(crate) fn radical_inverse(base: u64, mut index: u64) -> f64 {
+    let inv_base = 1.0 / base as f64;
+    let mut inv_base_n = 1.0;
+    let mut reversed = 0u64;
+    while index > 0 {
+        reversed = reversed * base + index % base;
+        index /= base;
+        inv_base_n *= inv_base;
+    }
+    (reversed as f64 * inv_base_n).min(1.0 - f64::EPSILON / 2.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn radical_inverse_mirrors_digits() {
+        assert_eq!(radical_inverse(2, 1), 0.5);
+        assert_eq!(radical_inverse(2, 6), 0.375);
+        assert!((radical_inverse(3, 5) - 7.0 / 9.0).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,30 @@
+use crate::sampler::sampler::{SampleState, Sampler};
+
+/// Uniform random values with no structure between samples.
+#[derive(Debug, Clone)]
+pub struct IndependentSampler {
+    state: SampleState,
+}
+
+impl IndependentSampler {
+    pub fn new(seed: u64) -> Self {
+        IndependentSampler {
+            state: SampleState::new(seed),
+        }
+    }
+}
+
+impl Sampler for IndependentSampler {
+    fn start_sample(&mut self, pixel: (usize, usize), sample_index: usize) {
+        self.state.start_sample(pixel, sample_index);
+    }
+
+    fn set_dimension(&mut self, dimension: usize) {
+        self.state.dimension = dimension;
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        self.state.dimension += 1;
+        self.state.random(self.state.dimension - 1)
+    }
+}
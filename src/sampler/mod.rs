@@ -0,0 +1,6 @@
+pub mod halton;
+pub mod independent;
+#[allow(clippy::module_inception)]
+pub mod sampler;
+pub mod sobol;
+pub mod stratified;
@@ -0,0 +1,183 @@
+use crate::sampler::halton::HaltonSampler;
+use crate::sampler::independent::IndependentSampler;
+use crate::sampler::sobol::SobolSampler;
+use crate::sampler::stratified::StratifiedSampler;
+use rand::{Rng, RngCore};
+
+/// First dimension of the 2D sample that jitters the position inside a pixel.
+pub const PIXEL_DIMENSION: usize = 0;
+/// First dimension of the 2D sample that picks a point on the lens.
+pub const LENS_DIMENSION: usize = 2;
+/// The 1D sample that picks a time inside the shutter interval.
+pub const TIME_DIMENSION: usize = 4;
+/// First dimension reserved for scattering at the first bounce; each later bounce starts
+/// [`BSDF_DIMENSIONS_PER_BOUNCE`] further on.
+pub const BSDF_DIMENSION: usize = 5;
+pub const BSDF_DIMENSIONS_PER_BOUNCE: usize = 3;
+
+/// A source of sample values in `[0, 1)`.
+///
+/// Samplers that do better than independent random numbers need to know which sample of which
+/// pixel is being taken and which dimension of it each value is for, so the renderer announces
+/// both. Any `RngCore` is also a `Sampler` that ignores them.
+pub trait Sampler {
+    fn start_sample(&mut self, pixel: (usize, usize), sample_index: usize);
+    /// Moves to `dimension`; later values come from the dimensions following it.
+    fn set_dimension(&mut self, dimension: usize);
+    fn get_1d(&mut self) -> f64;
+    fn get_2d(&mut self) -> (f64, f64) {
+        (self.get_1d(), self.get_1d())
+    }
+}
+
+impl<R: RngCore> Sampler for R {
+    fn start_sample(&mut self, _pixel: (usize, usize), _sample_index: usize) {}
+
+    fn set_dimension(&mut self, _dimension: usize) {}
+
+    fn get_1d(&mut self) -> f64 {
+        self.gen()
+    }
+}
+
+/// Which [`Sampler`] a render uses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SamplerKind {
+    Independent,
+    /// Jittered strata, shuffled independently for every dimension.
+    Stratified,
+    /// Halton points, randomly rotated per pixel.
+    Halton,
+    /// Sobol points, randomly scrambled per pixel.
+    Sobol,
+}
+
+impl SamplerKind {
+    /// `samples_per_pixel` sizes the strata of a stratified sampler; the others ignore it.
+    pub fn build(&self, samples_per_pixel: usize, seed: u64) -> Box<dyn Sampler> {
+        match self {
+            Self::Independent => Box::new(IndependentSampler::new(seed)),
+            Self::Stratified => Box::new(StratifiedSampler::new(samples_per_pixel, seed)),
+            Self::Halton => Box::new(HaltonSampler::new(seed)),
+            Self::Sobol => Box::new(SobolSampler::new(seed)),
+        }
+    }
+}
+
+/// Per-sample state shared by the samplers. Random values are hashed from the seed, pixel,
+/// sample index and dimension, so they do not depend on the order anything is rendered in.
+#[derive(Debug, Clone)]
+pub(crate) struct SampleState {
+    pub(crate) seed: u64,
+    pub(crate) pixel: (usize, usize),
+    pub(crate) sample_index: usize,
+    pub(crate) dimension: usize,
+}
+
+impl SampleState {
+    pub(crate) fn new(seed: u64) -> Self {
+        SampleState {
+            seed,
+            pixel: (0, 0),
+            sample_index: 0,
+            dimension: 0,
+        }
+    }
+
+    pub(crate) fn start_sample(&mut self, pixel: (usize, usize), sample_index: usize) {
+        self.pixel = pixel;
+        self.sample_index = sample_index;
+        self.dimension = 0;
+    }
+
+    /// A uniform random value fixed for the current sample and `dimension`.
+    pub(crate) fn random(&self, dimension: usize) -> f64 {
+        to_unit(hash(&[
+            self.seed,
+            self.pixel.0 as u64,
+            self.pixel.1 as u64,
+            self.sample_index as u64,
+            dimension as u64,
+        ]))
+    }
+
+    /// A value fixed for the current pixel and `dimension`, for scrambling.
+    pub(crate) fn pixel_hash(&self, dimension: usize) -> u64 {
+        hash(&[
+            self.seed,
+            self.pixel.0 as u64,
+            self.pixel.1 as u64,
+            dimension as u64,
+        ])
+    }
+}
+
+/// Mixes `values` into one well-distributed 64-bit value, using the SplitMix64 finalizer.
+pub(crate) fn hash(values: &[u64]) -> u64 {
+    values.iter().fold(0x9e37_79b9_7f4a_7c15, |h, v| {
+        let mut z = (h ^ v).wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    })
+}
+
+pub(crate) fn to_unit(bits: u64) -> f64 {
+    (bits >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cells_hit(kind: SamplerKind, n: usize) -> usize {
+        let mut sampler = kind.build(n * n, 7);
+        let mut cells = vec![false; n * n];
+        for i in 0..n * n {
+            sampler.start_sample((3, 5), i);
+            sampler.set_dimension(PIXEL_DIMENSION);
+            let (u, v) = sampler.get_2d();
+            cells[(v * n as f64) as usize * n + (u * n as f64) as usize] = true;
+        }
+        cells.iter().filter(|c| **c).count()
+    }
+
+    #[test]
+    fn structured_samplers_fill_every_cell() {
+        for kind in [SamplerKind::Stratified, SamplerKind::Sobol] {
+            assert_eq!(cells_hit(kind, 4), 16, "{:?}", kind);
+        }
+    }
+
+    #[test]
+    fn halton_fills_every_interval() {
+        let mut sampler = SamplerKind::Halton.build(16, 7);
+        let mut intervals = [false; 16];
+        for i in 0..16 {
+            sampler.start_sample((3, 5), i);
+            intervals[(sampler.get_1d() * 16.0) as usize] = true;
+        }
+        assert!(intervals.iter().all(|i| *i));
+    }
+
+    #[test]
+    fn samples_depend_only_on_pixel_index_and_dimension() {
+        for kind in [
+            SamplerKind::Independent,
+            SamplerKind::Stratified,
+            SamplerKind::Halton,
+            SamplerKind::Sobol,
+        ] {
+            let mut a = kind.build(16, 1);
+            let mut b = kind.build(16, 1);
+            a.start_sample((1, 2), 3);
+            b.start_sample((9, 9), 0);
+            b.start_sample((1, 2), 3);
+            b.set_dimension(6);
+            b.get_1d();
+            b.set_dimension(0);
+
+            assert_eq!(a.get_2d(), b.get_2d(), "{:?}", kind);
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use crate::sampler::sampler::{SampleState, Sampler};
+
+/// Degree, polynomial coefficients and initial direction numbers for dimensions 2 onwards, from
+/// Joe and Kuo's `new-joe-kuo-6.21201` table.
+const DIRECTIONS: [(u32, u32, &[u32]); 15] = [
+    (1, 0, &[1]),
+    (2, 1, &[1, 3]),
+    (3, 1, &[1, 3, 1]),
+    (3, 2, &[1, 1, 1]),
+    (4, 1, &[1, 1, 3, 3]),
+    (4, 4, &[1, 3, 5, 13]),
+    (5, 2, &[1, 1, 5, 5, 17]),
+    (5, 4, &[1, 1, 5, 5, 5]),
+    (5, 7, &[1, 1, 7, 11, 19]),
+    (5, 11, &[1, 1, 5, 1, 1]),
+    (5, 13, &[1, 1, 1, 3, 11]),
+    (5, 14, &[1, 3, 5, 5, 31]),
+    (6, 1, &[1, 3, 3, 9, 7, 49]),
+    (6, 13, &[1, 1, 1, 15, 21, 21]),
+    (6, 16, &[1, 3, 1, 13, 27, 49]),
+];
+const DIMENSIONS: usize = DIRECTIONS.len() + 1;
+const BITS: usize = 32;
+
+/// The Sobol sequence with a random digital shift per pixel and dimension. Dimensions past the
+/// direction number table get independent random values.
+#[derive(Debug, Clone)]
+pub struct SobolSampler {
+    state: SampleState,
+    directions: Vec<[u32; BITS]>,
+}
+
+impl SobolSampler {
+    pub fn new(seed: u64) -> Self {
+        SobolSampler {
+            state: SampleState::new(seed),
+            directions: direction_numbers(),
+        }
+    }
+}
+
+impl Sampler for SobolSampler {
+    fn start_sample(&mut self, pixel: (usize, usize), sample_index: usize) {
+        self.state.start_sample(pixel, sample_index);
+    }
+
+    fn set_dimension(&mut self, dimension: usize) {
+        self.state.dimension = dimension;
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let dimension = self.state.dimension;
+        self.state.dimension += 1;
+
+        if dimension >= DIMENSIONS {
+            return self.state.random(dimension);
+        }
+
+        let scramble = self.state.pixel_hash(dimension) as u32;
+        let bits = sobol(&self.directions[dimension], self.state.sample_index as u32) ^ scramble;
+        bits as f64 / (1u64 << BITS) as f64
+    }
+}
+
+fn sobol(directions: &[u32; BITS], index: u32) -> u32 {
+    directions
+        .iter()
+        .enumerate()
+        .filter(|(bit, _)| index >> bit & 1 == 1)
+        .fold(0, |x, (_, v)| x ^ v)
+}
+
+fn direction_numbers() -> Vec<[u32; BITS]> {
+    let mut table = Vec::with_capacity(DIMENSIONS);
+
+    let mut first = [0u32; BITS];
+    for (k, v) in first.iter_mut().enumerate() {
+        *v = 1 << (BITS - 1 - k);
+    }
+    table.push(first);
+
+    for (degree, coefficients, initial) in DIRECTIONS.iter() {
+        let s = *degree as usize;
+        let mut v = [0u32; BITS];
+        for k in 0..BITS {
+            v[k] = if k < s {
+                initial[k] << (BITS - 1 - k)
+            } else {
+                let mut x = v[k - s] ^ (v[k - s] >> s);
+                for j in 1..s {
+                    if coefficients >> (s - 1 - j) & 1 == 1 {
+                        x ^= v[k - j];
+                    }
+                }
+                x
+            };
+        }
+        table.push(v);
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_points_match_the_unscrambled_sequence() {
+        let directions = direction_numbers();
+        let point = |i: u32| {
+            (
+                sobol(&directions[0], i) as f64 / 2f64.powi(32),
+                sobol(&directions[1], i) as f64 / 2f64.powi(32),
+            )
+        };
+
+        assert_eq!(point(1), (0.5, 0.5));
+        assert_eq!(point(2), (0.25, 0.75));
+        assert_eq!(point(3), (0.75, 0.25));
+    }
+
+    #[test]
+    fn each_dimension_is_stratified_in_halves() {
+        let directions = direction_numbers();
+        for d in &directions {
+            let low = (0..8).filter(|i| sobol(d, *i) < 1 << 31).count();
+            assert_eq!(low, 4);
+        }
+    }
+}
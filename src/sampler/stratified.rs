@@ -0,0 +1,101 @@
+use crate::sampler::sampler::{SampleState, Sampler};
+
+/// Splits every dimension into as many strata as there are samples per pixel and puts one
+/// jittered sample in each, pairing 2D values on a grid. Strata are visited in a different
+/// shuffled order for every pixel and dimension, so dimensions stay uncorrelated. Samples past
+/// `samples_per_pixel` start a freshly shuffled round.
+#[derive(Debug, Clone)]
+pub struct StratifiedSampler {
+    samples_per_pixel: usize,
+    state: SampleState,
+}
+
+impl StratifiedSampler {
+    pub fn new(samples_per_pixel: usize, seed: u64) -> Self {
+        StratifiedSampler {
+            samples_per_pixel: samples_per_pixel.max(1),
+            state: SampleState::new(seed),
+        }
+    }
+
+    fn stratum(&self, strata: usize) -> usize {
+        let round = self.state.sample_index / strata;
+        let scramble = self.state.pixel_hash(self.state.dimension) ^ round as u64;
+        permute(
+            (self.state.sample_index % strata) as u32,
+            strata as u32,
+            scramble as u32,
+        ) as usize
+    }
+}
+
+impl Sampler for StratifiedSampler {
+    fn start_sample(&mut self, pixel: (usize, usize), sample_index: usize) {
+        self.state.start_sample(pixel, sample_index);
+    }
+
+    fn set_dimension(&mut self, dimension: usize) {
+        self.state.dimension = dimension;
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let strata = self.samples_per_pixel;
+        let stratum = self.stratum(strata);
+        let jitter = self.state.random(self.state.dimension);
+        self.state.dimension += 1;
+
+        (stratum as f64 + jitter) / strata as f64
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        let nx = f64::sqrt(self.samples_per_pixel as f64).ceil() as usize;
+        let ny = self.samples_per_pixel.div_ceil(nx);
+        let cell = self.stratum(nx * ny);
+        let jitter = (
+            self.state.random(self.state.dimension),
+            self.state.random(self.state.dimension + 1),
+        );
+        self.state.dimension += 2;
+
+        (
+            ((cell % nx) as f64 + jitter.0) / nx as f64,
+            ((cell / nx) as f64 + jitter.1) / ny as f64,
+        )
+    }
+}
+
+/// A pseudo-random permutation of `0..l` chosen by `p`, from Kensler, "Correlated Multi-Jittered
+/// Sampling" (2013).
+pub(crate) fn permute(mut i: u32, l: u32, p: u32) -> u32 {
+    let mut w = l.saturating_sub(1);
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+
+    loop {
+        i ^= p;
+        i = i.wrapping_mul(0xe170_893d);
+        i ^= p >> 16;
+        i ^= (i & w) >> 4;
+        i ^= p >> 8;
+        i = i.wrapping_mul(0x0929_eb3f);
+        i ^= p >> 23;
+        i ^= (i & w) >> 1;
+        i = i.wrapping_mul(1 | p >> 27);
+        i = i.wrapping_mul(0x6935_fa69);
+        i ^= (i & w) >> 11;
+        i = i.wrapping_mul(0x74dc_b303);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0x9e50_1cc3);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0xc860_a3df);
+        i &= w;
+        i ^= i >> 5;
+        if i < l {
+            break;
+        }
+    }
+    (i.wrapping_add(p)) % l
+}
@@ -0,0 +1,435 @@
+//! Standard scenes for examples, benchmarks and tests. Each is built from a seed that also goes
+//! into its recommended settings, so the same seed always gives the same picture.
+
+use crate::camera::Camera;
+use crate::environment::environment::Environment;
+use crate::environment::gradient_sky::GradientSky;
+use crate::environment::uniform::Uniform;
+use crate::hit_objects::{HitObject, HitObjects};
+use crate::material::dielectric::Dielectric;
+use crate::material::diffuse_light::DiffuseLight;
+use crate::material::lambertian::Lambertian;
+use crate::material::material::Material;
+use crate::material::metal::Metal;
+use crate::moving_sphere::MovingSphere;
+use crate::render::RenderSettings;
+use crate::sphere::Sphere;
+use crate::texture::checker::Checker;
+use crate::vector3::{Color, Point3, Vector3};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::sync::Arc;
+
+/// Walls are spheres this large, flat enough to pass for planes.
+const WALL_RADIUS: f64 = 1e5;
+
+pub struct Scene {
+    pub world: HitObjects,
+    pub camera: Camera,
+    pub environment: Box<dyn Environment>,
+    pub settings: RenderSettings,
+}
+
+fn settings(width: usize, height: usize, samples_per_pixel: usize, seed: u64) -> RenderSettings {
+    let mut settings = RenderSettings::new(width, height);
+    settings.samples_per_pixel = samples_per_pixel;
+    settings.seed = seed;
+    settings
+}
+
+fn sphere(center: Point3, radius: f64, material: Arc<dyn Material>) -> HitObject {
+    HitObject::Sphere(Sphere::new(center, radius, material))
+}
+
+fn ground(material: Arc<dyn Material>) -> HitObject {
+    sphere(Point3::new_y(-1000.0), 1000.0, material)
+}
+
+/// The glass, diffuse and metal spheres in the middle of the book's final scene.
+fn add_three_spheres(world: &mut HitObjects) {
+    world.add(sphere(
+        Point3::new_y(1.0),
+        1.0,
+        Arc::new(Dielectric::new(1.5)),
+    ));
+    world.add(sphere(
+        Point3::new(-4.0, 1.0, 0.0),
+        1.0,
+        Arc::new(Lambertian::new(Color::new(0.4, 0.2, 0.1))),
+    ));
+    world.add(sphere(
+        Point3::new(4.0, 1.0, 0.0),
+        1.0,
+        Arc::new(Metal::new(Color::new(0.7, 0.6, 0.5), 0.0)),
+    ));
+}
+
+fn book_camera(vfov: f64) -> Camera {
+    Camera::new(
+        Point3::new(13.0, 2.0, 3.0),
+        Point3::zero(),
+        Vector3::new_y(1.0),
+        vfov,
+        3.0 / 2.0,
+        0.1,
+        10.0,
+        0.0,
+        1.0,
+    )
+}
+
+/// The final scene of the first book: three large spheres among hundreds of small random ones.
+pub fn random_spheres(seed: u64) -> Scene {
+    small_spheres(seed, false)
+}
+
+/// The first scene of the second book: [`random_spheres`] with the diffuse small spheres
+/// bouncing upwards while the shutter is open.
+pub fn bouncing_spheres(seed: u64) -> Scene {
+    small_spheres(seed, true)
+}
+
+fn small_spheres(seed: u64, bouncing: bool) -> Scene {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut world = HitObjects::new();
+    world.add(ground(Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)))));
+
+    for a in -11..12 {
+        for b in -11..12 {
+            let choose_mat = rng.gen::<f64>();
+            let center = Point3::new(
+                (a as f64) + 0.9 * rng.gen::<f64>(),
+                0.2,
+                (b as f64) + 0.9 * rng.gen::<f64>(),
+            );
+            if (&center - &Point3::new(4.0, 0.2, 0.0)).length() <= 0.9 {
+                continue;
+            }
+
+            if choose_mat < 0.8 {
+                let albedo = Color::from(
+                    Vector3::random(&mut rng).hadamard_product(&Vector3::random(&mut rng)),
+                );
+                let material = Arc::new(Lambertian::new(albedo));
+                if bouncing {
+                    let center1 = &center + Vector3::new_y(rng.gen_range(0.0..0.5));
+                    world.add(HitObject::MovingSphere(MovingSphere::new(
+                        center, center1, 0.2, 0.0, 1.0, material,
+                    )));
+                } else {
+                    world.add(sphere(center, 0.2, material));
+                }
+            } else if choose_mat < 0.95 {
+                let albedo = Color::random_range(&mut rng, 0.5..1.0);
+                let fuzz = rng.gen();
+                world.add(sphere(center, 0.2, Arc::new(Metal::new(albedo, fuzz))));
+            } else {
+                world.add(sphere(center, 0.2, Arc::new(Dielectric::new(1.5))));
+            }
+        }
+    }
+    add_three_spheres(&mut world);
+
+    Scene {
+        world,
+        camera: book_camera(20.0),
+        environment: Box::new(GradientSky::default()),
+        settings: settings(600, 400, 100, seed),
+    }
+}
+
+/// Just the three large spheres of [`random_spheres`] on its ground, framed more closely. Cheap
+/// enough to render without a BVH.
+pub fn three_spheres(seed: u64) -> Scene {
+    let mut world = HitObjects::new();
+    world.add(ground(Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)))));
+    add_three_spheres(&mut world);
+
+    Scene {
+        world,
+        camera: book_camera(30.0),
+        environment: Box::new(GradientSky::default()),
+        settings: settings(600, 400, 100, seed),
+    }
+}
+
+/// The 555 unit Cornell box of the second book, lit by a small light in its ceiling, with a
+/// diffuse and a glass sphere in place of the boxes. Everything is made of spheres: the walls
+/// are huge ones and the light is the bottom of one poking through the ceiling.
+pub fn cornell_box(seed: u64) -> Scene {
+    let red = Arc::new(Lambertian::new(Color::new(0.65, 0.05, 0.05)));
+    let white = Arc::new(Lambertian::new(Color::new(0.73, 0.73, 0.73)));
+    let green = Arc::new(Lambertian::new(Color::new(0.12, 0.45, 0.15)));
+    let light = Arc::new(DiffuseLight::new(Color::new_all(15.0)));
+
+    let mut world = HitObjects::new();
+    let middle = 555.0 / 2.0;
+    let walls: [(Point3, Arc<dyn Material>); 5] = [
+        (Point3::new(-WALL_RADIUS, middle, middle), green),
+        (Point3::new(555.0 + WALL_RADIUS, middle, middle), red),
+        (Point3::new(middle, -WALL_RADIUS, middle), white.clone()),
+        (
+            Point3::new(middle, 555.0 + WALL_RADIUS, middle),
+            white.clone(),
+        ),
+        (
+            Point3::new(middle, middle, 555.0 + WALL_RADIUS),
+            white.clone(),
+        ),
+    ];
+    for (center, material) in walls {
+        world.add(sphere(center, WALL_RADIUS, material));
+    }
+
+    // Dips 2 units below the ceiling, showing a disk about 125 units across
+    let light_radius = 1000.0;
+    world.add(sphere(
+        Point3::new(middle, 555.0 + light_radius - 2.0, middle),
+        light_radius,
+        light,
+    ));
+
+    world.add(sphere(Point3::new(380.0, 100.0, 370.0), 100.0, white));
+    world.add(sphere(
+        Point3::new(180.0, 90.0, 190.0),
+        90.0,
+        Arc::new(Dielectric::new(1.5)),
+    ));
+
+    let camera = Camera::new(
+        Point3::new(middle, middle, -800.0),
+        Point3::new(middle, middle, 0.0),
+        Vector3::new_y(1.0),
+        40.0,
+        1.0,
+        0.0,
+        10.0,
+        0.0,
+        1.0,
+    );
+    Scene {
+        world,
+        camera,
+        environment: Box::new(Uniform::default()),
+        settings: settings(600, 600, 200, seed),
+    }
+}
+
+/// Rows of spheres on a checkered floor, sweeping one material parameter each: metal from
+/// polished to rough, glass from a low to a high index of refraction, and diffuse spheres of
+/// random colors.
+pub fn shader_balls(seed: u64) -> Scene {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut world = HitObjects::new();
+    world.add(ground(Arc::new(Lambertian::new_texture(Arc::new(
+        Checker::new(Color::new_all(0.2), Color::new_all(0.8), 1.0),
+    )))));
+
+    for column in 0..5 {
+        let x = 2.0 * column as f64 - 4.0;
+        let t = column as f64 / 4.0;
+
+        let fuzz = t;
+        world.add(sphere(
+            Point3::new(x, 0.6, 0.0),
+            0.6,
+            Arc::new(Metal::new(Color::new(0.8, 0.8, 0.8), fuzz)),
+        ));
+
+        let index_of_refraction = 1.1 + 1.3 * t;
+        world.add(sphere(
+            Point3::new(x, 0.6, -2.5),
+            0.6,
+            Arc::new(Dielectric::new(index_of_refraction)),
+        ));
+
+        let albedo = Color::random_range(&mut rng, 0.1..0.9);
+        world.add(sphere(
+            Point3::new(x, 0.6, -5.0),
+            0.6,
+            Arc::new(Lambertian::new(albedo)),
+        ));
+    }
+
+    let camera = Camera::new(
+        Point3::new(0.0, 5.0, 8.0),
+        Point3::new(0.0, 0.3, -2.5),
+        Vector3::new_y(1.0),
+        40.0,
+        3.0 / 2.0,
+        0.0,
+        10.0,
+        0.0,
+        1.0,
+    );
+    Scene {
+        world,
+        camera,
+        environment: Box::new(GradientSky::default()),
+        settings: settings(600, 400, 100, seed),
+    }
+}
+
+/// Checkers of different sizes on the floor and on spheres, one of them a textured light, next
+/// to a plain sphere for comparison. The seed picks the colors.
+pub fn texture_test(seed: u64) -> Scene {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut color = || Color::random_range(&mut rng, 0.1..0.9);
+    let checker = |even: Color, odd: Color, size: f64| Arc::new(Checker::new(even, odd, size));
+
+    let mut world = HitObjects::new();
+    world.add(ground(Arc::new(Lambertian::new_texture(checker(
+        color(),
+        color(),
+        1.0,
+    )))));
+    world.add(sphere(
+        Point3::new(-2.2, 1.0, 0.0),
+        1.0,
+        Arc::new(Lambertian::new_texture(checker(color(), color(), 0.5))),
+    ));
+    world.add(sphere(
+        Point3::new_y(1.0),
+        1.0,
+        Arc::new(Lambertian::new_texture(checker(color(), color(), 0.1))),
+    ));
+    world.add(sphere(
+        Point3::new(2.2, 1.0, 0.0),
+        1.0,
+        Arc::new(Lambertian::new(color())),
+    ));
+    world.add(sphere(
+        Point3::new(0.0, 3.0, -2.0),
+        0.6,
+        Arc::new(DiffuseLight::new_texture(checker(
+            Color::new_all(4.0),
+            Color::black(),
+            0.3,
+        ))),
+    ));
+
+    let camera = Camera::new(
+        Point3::new(0.0, 3.0, 9.0),
+        Point3::new_y(1.2),
+        Vector3::new_y(1.0),
+        35.0,
+        3.0 / 2.0,
+        0.0,
+        10.0,
+        0.0,
+        1.0,
+    );
+    Scene {
+        world,
+        camera,
+        environment: Box::new(GradientSky::new(
+            Color::new_all(0.5),
+            Color::new(0.25, 0.35, 0.5),
+        )),
+        settings: settings(600, 400, 100, seed),
+    }
+}
+
+/// A dim room-less scene lit only by dozens of small colored lights scattered over the floor,
+/// around a diffuse, a metal and a glass sphere. Stresses light sampling: each light is hit by
+/// few paths.
+pub fn many_lights(seed: u64) -> Scene {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut world = HitObjects::new();
+    world.add(ground(Arc::new(Lambertian::new(Color::new_all(0.4)))));
+
+    for _ in 0..64 {
+        let center = Point3::new(rng.gen_range(-8.0..8.0), 0.15, rng.gen_range(-8.0..4.0));
+        if (&center - &Point3::new(0.0, 0.15, 0.0)).length() < 3.5 {
+            continue;
+        }
+        let emit = Vector3::random_range(&mut rng, 0.2..1.0) * rng.gen_range(5.0..20.0);
+        world.add(sphere(
+            center,
+            0.15,
+            Arc::new(DiffuseLight::new(Color::from(emit))),
+        ));
+    }
+
+    world.add(sphere(
+        Point3::new(-2.2, 1.0, 0.0),
+        1.0,
+        Arc::new(Lambertian::new(Color::new(0.7, 0.7, 0.7))),
+    ));
+    world.add(sphere(
+        Point3::new_y(1.0),
+        1.0,
+        Arc::new(Metal::new(Color::new(0.8, 0.8, 0.8), 0.05)),
+    ));
+    world.add(sphere(
+        Point3::new(2.2, 1.0, 0.0),
+        1.0,
+        Arc::new(Dielectric::new(1.5)),
+    ));
+
+    let camera = Camera::new(
+        Point3::new(0.0, 4.0, 10.0),
+        Point3::new_y(0.5),
+        Vector3::new_y(1.0),
+        40.0,
+        3.0 / 2.0,
+        0.0,
+        10.0,
+        0.0,
+        1.0,
+    );
+    Scene {
+        world,
+        camera,
+        environment: Box::new(Uniform::new(Color::new_all(0.01))),
+        settings: settings(600, 400, 200, seed),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::render;
+
+    fn preview(scene: &Scene) -> crate::image::Image {
+        let mut settings = scene.settings.clone();
+        settings.image_width = 12;
+        settings.image_height = 8;
+        settings.samples_per_pixel = 2;
+        render(
+            &scene.world,
+            &scene.camera,
+            scene.environment.as_ref(),
+            &settings,
+        )
+    }
+
+    #[test]
+    fn every_scene_shows_something() {
+        let scenes: [fn(u64) -> Scene; 7] = [
+            random_spheres,
+            bouncing_spheres,
+            three_spheres,
+            cornell_box,
+            shader_balls,
+            texture_test,
+            many_lights,
+        ];
+        for scene in scenes {
+            let scene = scene(1);
+            assert_eq!(scene.settings.seed, 1);
+            let image = preview(&scene);
+            assert!(image.pixels().iter().any(|c| c.r() + c.g() + c.b() > 0.0));
+        }
+    }
+
+    #[test]
+    fn seeds_reproduce_scenes() {
+        let count = |scene: Scene| scene.world.0.len();
+        assert_eq!(preview(&random_spheres(7)), preview(&random_spheres(7)));
+        assert_ne!(
+            (count(random_spheres(7)), count(many_lights(7))),
+            (count(random_spheres(8)), count(many_lights(8)))
+        );
+    }
+}
@@ -0,0 +1,184 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The order in which tiles are handed out to the worker threads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TileOrder {
+    /// Left to right, top to bottom.
+    Scanline,
+    /// Outwards from the center of the image, so the subject shows up first.
+    #[default]
+    Spiral,
+    /// Along a Hilbert curve, which keeps consecutive tiles next to each other.
+    Hilbert,
+}
+
+/// A rectangle of pixels rendered as one task. Rows count down from the top of the image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tile {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+/// Splits a `width` by `height` image into tiles of at most `tile_size` pixels square, in `order`.
+pub fn tiles(width: usize, height: usize, tile_size: usize, order: TileOrder) -> Vec<Tile> {
+    let tile_size = tile_size.max(1);
+    let columns = width.div_ceil(tile_size);
+    let rows = height.div_ceil(tile_size);
+
+    let mut cells: Vec<(usize, usize)> = (0..rows)
+        .flat_map(|r| (0..columns).map(move |c| (c, r)))
+        .collect();
+
+    match order {
+        TileOrder::Scanline => {}
+        TileOrder::Spiral => {
+            let cx = (columns as f64 - 1.0) / 2.0;
+            let cy = (rows as f64 - 1.0) / 2.0;
+            cells.sort_by(|&(ac, ar), &(bc, br)| {
+                let key = |c: usize, r: usize| {
+                    let (dx, dy) = (c as f64 - cx, r as f64 - cy);
+                    (dx.abs().max(dy.abs()), f64::atan2(dy, dx))
+                };
+                key(ac, ar).partial_cmp(&key(bc, br)).unwrap()
+            });
+        }
+        TileOrder::Hilbert => {
+            let n = columns.max(rows).next_power_of_two();
+            cells.sort_by_key(|&(c, r)| hilbert_index(n, c, r));
+        }
+    }
+
+    cells
+        .into_iter()
+        .map(|(c, r)| {
+            let x = c * tile_size;
+            let y = r * tile_size;
+            Tile {
+                x,
+                y,
+                width: tile_size.min(width - x),
+                height: tile_size.min(height - y),
+            }
+        })
+        .collect()
+}
+
+/// Distance of `(x, y)` along the Hilbert curve filling an `n` by `n` grid, `n` a power of two.
+fn hilbert_index(n: usize, mut x: usize, mut y: usize) -> usize {
+    let mut d = 0;
+    let mut s = n / 2;
+    while s > 0 {
+        let rx = usize::from(x & s > 0);
+        let ry = usize::from(y & s > 0);
+        d += s * s * ((3 * rx) ^ ry);
+        if ry == 0 {
+            if rx == 1 {
+                x = s - 1 - (x & (s - 1));
+                y = s - 1 - (y & (s - 1));
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        x &= s - 1;
+        y &= s - 1;
+        s /= 2;
+    }
+    d
+}
+
+/// Reported after every finished tile.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Progress {
+    pub completed_tiles: usize,
+    pub total_tiles: usize,
+    pub elapsed: Duration,
+    /// Extrapolated from the time taken so far.
+    pub eta: Duration,
+}
+
+impl Progress {
+    pub(crate) fn new(completed_tiles: usize, total_tiles: usize, elapsed: Duration) -> Self {
+        let remaining = total_tiles - completed_tiles;
+        let eta = if completed_tiles == 0 {
+            Duration::ZERO
+        } else {
+            elapsed.mul_f64(remaining as f64 / completed_tiles as f64)
+        };
+
+        Progress {
+            completed_tiles,
+            total_tiles,
+            elapsed,
+            eta,
+        }
+    }
+
+    pub fn fraction(&self) -> f64 {
+        self.completed_tiles as f64 / self.total_tiles.max(1) as f64
+    }
+}
+
+/// Stops a render from starting any more tiles once tripped. Clones share the same flag, so one
+/// can be handed to another thread.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn covered(tiles: &[Tile], width: usize, height: usize) -> Vec<usize> {
+        let mut coverage = vec![0; width * height];
+        for t in tiles {
+            for y in t.y..t.y + t.height {
+                for x in t.x..t.x + t.width {
+                    coverage[y * width + x] += 1;
+                }
+            }
+        }
+        coverage
+    }
+
+    #[test]
+    fn every_order_covers_each_pixel_once() {
+        for order in &[TileOrder::Scanline, TileOrder::Spiral, TileOrder::Hilbert] {
+            let tiles = tiles(37, 21, 8, *order);
+            assert_eq!(tiles.len(), 15);
+            assert!(
+                covered(&tiles, 37, 21).iter().all(|c| *c == 1),
+                "{:?}",
+                order
+            );
+        }
+    }
+
+    #[test]
+    fn spiral_starts_in_the_middle_and_hilbert_steps_to_neighbours() {
+        let spiral = tiles(48, 48, 16, TileOrder::Spiral);
+        assert_eq!((spiral[0].x, spiral[0].y), (16, 16));
+
+        let hilbert = tiles(64, 64, 16, TileOrder::Hilbert);
+        for pair in hilbert.windows(2) {
+            let dx = (pair[0].x as isize - pair[1].x as isize).abs();
+            let dy = (pair[0].y as isize - pair[1].y as isize).abs();
+            assert_eq!(dx + dy, 16);
+        }
+    }
+}
@@ -21,6 +21,11 @@ impl Sphere {
             material,
         }
     }
+
+    /// The point of the surface closest to `point`.
+    pub fn closest_point(&self, point: &Point3) -> Point3 {
+        closest_point_on_sphere(&self.center, self.radius, point)
+    }
 }
 
 pub(crate) fn hit_sphere(
@@ -55,6 +60,18 @@ pub(crate) fn hit_sphere(
     })
 }
 
+pub(crate) fn closest_point_on_sphere(center: &Point3, radius: f64, point: &Point3) -> Point3 {
+    let offset = point - center;
+    let length = offset.length();
+    // From the center every point of the surface is as close, so any will do
+    let direction = if length > 0.0 {
+        offset / length
+    } else {
+        Vector3::new_x(1.0)
+    };
+    center + direction * radius
+}
+
 pub(crate) fn sphere_bounding_box(center: &Point3, radius: f64) -> AABB {
     AABB::new(
         center - &Vector3::new(radius, radius, radius),
@@ -79,10 +96,30 @@ impl Hit for Sphere {
     }
 
     fn nearest_squared(&self, point: &Point3) -> f64 {
-        (&self.center - point).length_squared() - self.radius.powi(2)
+        ((&self.center - point).length() - self.radius)
+            .max(0.0)
+            .powi(2)
     }
 
     fn farest_squared(&self, point: &Point3) -> f64 {
-        (&self.center - point).length_squared() + self.radius.powi(2)
+        ((&self.center - point).length() + self.radius).powi(2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::lambertian::Lambertian;
+    use crate::vector3::Color;
+
+    #[test]
+    fn distances_reach_the_surface_and_vanish_inside() {
+        let material = Arc::new(Lambertian::new(Color::white()));
+        let sphere = Sphere::new(Point3::new_x(1.0), 2.0, material);
+
+        assert_eq!(sphere.nearest_squared(&Point3::new_x(6.0)), 9.0);
+        assert_eq!(sphere.farest_squared(&Point3::new_x(6.0)), 49.0);
+        assert_eq!(sphere.nearest_squared(&Point3::new_x(1.5)), 0.0);
+        assert_eq!(sphere.farest_squared(&Point3::new_x(1.5)), 6.25);
     }
 }
@@ -0,0 +1,163 @@
+use crate::camera::Camera;
+use crate::environment::environment::Environment;
+use crate::hit::Hit;
+use crate::image::Image;
+use crate::render::{render, RenderSettings};
+use crate::vector3::{Color, Point3, Vector3};
+
+/// How the two eye images are packed into one picture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StereoLayout {
+    /// Left eye on the left, right eye on the right; the result is twice as wide.
+    SideBySide,
+    /// Left eye on top, right eye below; the result is twice as tall.
+    TopBottom,
+    /// Red channel from the left eye, green and blue from the right, for red/cyan glasses.
+    Anaglyph,
+}
+
+/// A pair of parallel perspective cameras that converge through off-axis image windows, so
+/// objects at `convergence_distance` appear at screen depth without keystone distortion.
+#[derive(Debug, Clone)]
+pub struct StereoRig {
+    center: Camera,
+    interocular_distance: f64,
+}
+
+impl StereoRig {
+    /// Arguments follow [`Camera::new`]. The focus distance of both eyes is the convergence
+    /// distance.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        look_from: Point3,
+        look_at: Point3,
+        up_vector: Vector3,
+        vertical_fov: f64,
+        aspect_ratio: f64,
+        aperture: f64,
+        interocular_distance: f64,
+        convergence_distance: f64,
+        time0: f64,
+        time1: f64,
+    ) -> Self {
+        StereoRig {
+            center: Camera::new(
+                look_from,
+                look_at,
+                up_vector,
+                vertical_fov,
+                aspect_ratio,
+                aperture,
+                convergence_distance,
+                time0,
+                time1,
+            ),
+            interocular_distance,
+        }
+    }
+
+    pub fn interocular_distance(&self) -> f64 {
+        self.interocular_distance
+    }
+
+    pub fn left(&self) -> Camera {
+        self.center
+            .shifted(&(self.center.u() * (-self.interocular_distance / 2.0)))
+    }
+
+    pub fn right(&self) -> Camera {
+        self.center
+            .shifted(&(self.center.u() * (self.interocular_distance / 2.0)))
+    }
+
+    /// Renders the left and right eye, each at the resolution in `settings`.
+    pub fn render(
+        &self,
+        world: &dyn Hit,
+        environment: &dyn Environment,
+        settings: &RenderSettings,
+    ) -> (Image, Image) {
+        (
+            render(world, &self.left(), environment, settings),
+            render(world, &self.right(), environment, settings),
+        )
+    }
+}
+
+/// Packs a left and right eye image of the same size into one image.
+pub fn compose(left: &Image, right: &Image, layout: StereoLayout) -> Image {
+    assert_eq!(
+        (left.width(), left.height()),
+        (right.width(), right.height())
+    );
+    let width = left.width();
+    let height = left.height();
+
+    match layout {
+        StereoLayout::SideBySide => {
+            let mut image = Image::new(width * 2, height);
+            for y in 0..height {
+                for x in 0..width {
+                    image.set_pixel(x, y, left.pixel(x, y).clone());
+                    image.set_pixel(x + width, y, right.pixel(x, y).clone());
+                }
+            }
+            image
+        }
+        StereoLayout::TopBottom => {
+            let mut pixels = left.pixels().to_vec();
+            pixels.extend_from_slice(right.pixels());
+            Image::from_pixels(width, height * 2, pixels)
+        }
+        StereoLayout::Anaglyph => Image::from_pixels(
+            width,
+            height,
+            left.pixels()
+                .iter()
+                .zip(right.pixels())
+                .map(|(l, r)| Color::new(l.r(), r.g(), r.b()))
+                .collect(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn eyes_are_separated_along_the_camera_right_axis() {
+        let rig = StereoRig::new(
+            Point3::zero(),
+            Point3::new_z(-1.0),
+            Vector3::new_y(1.0),
+            40.0,
+            1.0,
+            0.0,
+            0.064,
+            2.0,
+            0.0,
+            1.0,
+        );
+
+        assert_eq!(rig.left().origin(), &Point3::new_x(-0.032));
+        assert_eq!(rig.right().origin(), &Point3::new_x(0.032));
+    }
+
+    #[test]
+    fn compose_layouts() {
+        let left = Image::from_pixels(1, 1, vec![Color::red()]);
+        let right = Image::from_pixels(1, 1, vec![Color::new(0.0, 0.5, 1.0)]);
+
+        let sbs = compose(&left, &right, StereoLayout::SideBySide);
+        assert_eq!((sbs.width(), sbs.height()), (2, 1));
+        assert_eq!(sbs.pixel(1, 0), right.pixel(0, 0));
+
+        let tb = compose(&left, &right, StereoLayout::TopBottom);
+        assert_eq!((tb.width(), tb.height()), (1, 2));
+        assert_eq!(tb.pixel(0, 0), left.pixel(0, 0));
+
+        let anaglyph = compose(&left, &right, StereoLayout::Anaglyph);
+        assert_eq!(anaglyph.pixel(0, 0), &Color::new(1.0, 0.5, 1.0));
+    }
+}
@@ -0,0 +1,325 @@
+//! Counters of the work done while tracing, compiled in with the `telemetry` feature. Without it
+//! the counting functions do nothing and [`take`] returns empty [`Stats`].
+//!
+//! Each thread counts on its own and adds its counts to the process-wide totals when flushed.
+//! The renderer flushes after every tile; other threads should call [`flush`] when done.
+
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Stats {
+    /// Rays leaving the camera.
+    pub primary_rays: u64,
+    /// Rays scattered off surfaces.
+    pub secondary_rays: u64,
+    /// Calls to `Node::hit`.
+    pub bvh_node_visits: u64,
+    /// Ray-box tests, one per node visited.
+    pub aabb_tests: u64,
+    /// Ray-object intersection tests, inside a BVH or not.
+    pub primitive_tests: u64,
+    /// How many paths hit this many surfaces before they ended.
+    pub path_lengths: Vec<u64>,
+    /// Wall-clock time spent in each named phase, in the order the phases first ran.
+    pub phases: Vec<(String, Duration)>,
+}
+
+impl Stats {
+    pub const fn new() -> Self {
+        Stats {
+            primary_rays: 0,
+            secondary_rays: 0,
+            bvh_node_visits: 0,
+            aabb_tests: 0,
+            primitive_tests: 0,
+            path_lengths: Vec::new(),
+            phases: Vec::new(),
+        }
+    }
+
+    pub fn rays(&self) -> u64 {
+        self.primary_rays + self.secondary_rays
+    }
+
+    pub fn phase(&self, name: &str) -> Option<Duration> {
+        self.phases.iter().find(|(n, _)| n == name).map(|(_, d)| *d)
+    }
+
+    /// Rays traced per second of the `render` phase.
+    pub fn rays_per_second(&self) -> Option<f64> {
+        self.phase("render")
+            .map(|d| self.rays() as f64 / d.as_secs_f64())
+    }
+
+    pub fn aabb_tests_per_ray(&self) -> f64 {
+        self.aabb_tests as f64 / self.rays().max(1) as f64
+    }
+
+    pub fn primitive_tests_per_ray(&self) -> f64 {
+        self.primitive_tests as f64 / self.rays().max(1) as f64
+    }
+
+    pub fn merge(&mut self, other: &Stats) {
+        self.primary_rays += other.primary_rays;
+        self.secondary_rays += other.secondary_rays;
+        self.bvh_node_visits += other.bvh_node_visits;
+        self.aabb_tests += other.aabb_tests;
+        self.primitive_tests += other.primitive_tests;
+
+        if self.path_lengths.len() < other.path_lengths.len() {
+            self.path_lengths.resize(other.path_lengths.len(), 0);
+        }
+        for (total, count) in self.path_lengths.iter_mut().zip(&other.path_lengths) {
+            *total += count;
+        }
+
+        for (name, duration) in &other.phases {
+            self.add_phase(name, *duration);
+        }
+    }
+
+    fn add_phase(&mut self, name: &str, duration: Duration) {
+        match self.phases.iter_mut().find(|(n, _)| n == name) {
+            Some((_, total)) => *total += duration,
+            None => self.phases.push((name.to_string(), duration)),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        let path_lengths: Vec<_> = self.path_lengths.iter().map(u64::to_string).collect();
+        let phases: Vec<_> = self
+            .phases
+            .iter()
+            .map(|(name, d)| format!(r#""{}":{}"#, name, d.as_secs_f64()))
+            .collect();
+        let rays_per_second = self
+            .rays_per_second()
+            .map_or_else(|| "null".to_string(), |r| r.to_string());
+
+        format!(
+            concat!(
+                r#"{{"primary_rays":{},"secondary_rays":{},"#,
+                r#""bvh_node_visits":{},"aabb_tests":{},"primitive_tests":{},"#,
+                r#""rays_per_second":{},"path_lengths":[{}],"phases":{{{}}}}}"#
+            ),
+            self.primary_rays,
+            self.secondary_rays,
+            self.bvh_node_visits,
+            self.aabb_tests,
+            self.primitive_tests,
+            rays_per_second,
+            path_lengths.join(","),
+            phases.join(",")
+        )
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "rays: {} primary, {} secondary",
+            self.primary_rays, self.secondary_rays
+        )?;
+        if let Some(rate) = self.rays_per_second() {
+            writeln!(f, "rays per second: {:.0}", rate)?;
+        }
+        writeln!(
+            f,
+            "per ray: {:.2} box tests, {:.2} primitive tests ({} BVH node visits in all)",
+            self.aabb_tests_per_ray(),
+            self.primitive_tests_per_ray(),
+            self.bvh_node_visits
+        )?;
+        writeln!(f, "path lengths:")?;
+        for (length, count) in self.path_lengths.iter().enumerate() {
+            writeln!(f, "  {:>3}: {}", length, count)?;
+        }
+        writeln!(f, "phases:")?;
+        for (name, duration) in &self.phases {
+            writeln!(f, "  {}: {:.3}s", name, duration.as_secs_f64())?;
+        }
+        Ok(())
+    }
+}
+
+static TOTALS: Mutex<Stats> = Mutex::new(Stats::new());
+
+#[cfg(feature = "telemetry")]
+thread_local! {
+    static LOCAL: std::cell::RefCell<Stats> = const { std::cell::RefCell::new(Stats::new()) };
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Counter {
+    PrimaryRay,
+    SecondaryRay,
+    BvhNodeVisit,
+    AabbTest,
+    PrimitiveTest,
+}
+
+#[inline]
+pub(crate) fn count(counter: Counter) {
+    #[cfg(feature = "telemetry")]
+    LOCAL.with(|local| {
+        let mut local = local.borrow_mut();
+        match counter {
+            Counter::PrimaryRay => local.primary_rays += 1,
+            Counter::SecondaryRay => local.secondary_rays += 1,
+            Counter::BvhNodeVisit => local.bvh_node_visits += 1,
+            Counter::AabbTest => local.aabb_tests += 1,
+            Counter::PrimitiveTest => local.primitive_tests += 1,
+        }
+    });
+    #[cfg(not(feature = "telemetry"))]
+    let _ = counter;
+}
+
+#[inline]
+pub(crate) fn count_path(length: usize) {
+    #[cfg(feature = "telemetry")]
+    LOCAL.with(|local| {
+        let mut local = local.borrow_mut();
+        if local.path_lengths.len() <= length {
+            local.path_lengths.resize(length + 1, 0);
+        }
+        local.path_lengths[length] += 1;
+    });
+    #[cfg(not(feature = "telemetry"))]
+    let _ = length;
+}
+
+/// Runs `f` on this thread and returns how many ray-box and primitive tests it made, without
+/// adding them to the counts.
+#[cfg(feature = "telemetry")]
+pub(crate) fn measure<T>(f: impl FnOnce() -> T) -> (T, u64, u64) {
+    let before = LOCAL.with(|local| {
+        let local = local.borrow();
+        (local.aabb_tests, local.primitive_tests)
+    });
+    let result = f();
+    let (aabb_tests, primitive_tests) = LOCAL.with(|local| {
+        let mut local = local.borrow_mut();
+        let used = (
+            local.aabb_tests - before.0,
+            local.primitive_tests - before.1,
+        );
+        local.aabb_tests = before.0;
+        local.primitive_tests = before.1;
+        used
+    });
+    (result, aabb_tests, primitive_tests)
+}
+
+/// Runs `f`, adding the time it takes to the phase called `name`.
+pub fn phase<T>(name: &str, f: impl FnOnce() -> T) -> T {
+    let started = Instant::now();
+    let result = f();
+    if cfg!(feature = "telemetry") {
+        TOTALS.lock().unwrap().add_phase(name, started.elapsed());
+    }
+    result
+}
+
+/// Adds the counts of the calling thread to the totals.
+pub fn flush() {
+    #[cfg(feature = "telemetry")]
+    LOCAL.with(|local| {
+        let local = local.replace(Stats::new());
+        TOTALS.lock().unwrap().merge(&local);
+    });
+}
+
+/// The totals so far, without flushing or starting them over. The renderer flushes after every
+/// tile, so during a render this lags by the tiles in flight.
+pub fn totals() -> Stats {
+    TOTALS.lock().unwrap().clone()
+}
+
+/// Flushes the calling thread and returns the totals, starting them over.
+pub fn take() -> Stats {
+    flush();
+    std::mem::take(&mut *TOTALS.lock().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merging_adds_histograms_and_phases() {
+        let mut a = Stats {
+            primary_rays: 2,
+            path_lengths: vec![1, 1],
+            phases: vec![("render".to_string(), Duration::from_secs(1))],
+            ..Stats::new()
+        };
+        let b = Stats {
+            primary_rays: 2,
+            secondary_rays: 4,
+            path_lengths: vec![0, 1, 3],
+            phases: vec![
+                ("build".to_string(), Duration::from_secs(1)),
+                ("render".to_string(), Duration::from_secs(1)),
+            ],
+            ..Stats::new()
+        };
+
+        a.merge(&b);
+
+        assert_eq!(a.path_lengths, vec![1, 2, 3]);
+        assert_eq!(a.phase("render"), Some(Duration::from_secs(2)));
+        assert_eq!(a.rays_per_second(), Some(4.0));
+        assert!(a
+            .to_json()
+            .contains(r#""path_lengths":[1,2,3],"phases":{"render":2,"build":1}"#));
+    }
+
+    #[cfg(feature = "telemetry")]
+    #[test]
+    fn rendering_counts_rays_and_bvh_work() {
+        use crate::bvh::node::Node;
+        use crate::camera::Camera;
+        use crate::environment::gradient_sky::GradientSky;
+        use crate::hit_objects::HitObject;
+        use crate::material::lambertian::Lambertian;
+        use crate::render::{render, RenderSettings};
+        use crate::sphere::Sphere;
+        use crate::vector3::{Color, Point3};
+        use rand::thread_rng;
+        use std::sync::Arc;
+
+        let objects: Vec<_> = (0..4)
+            .map(|i| {
+                HitObject::Sphere(Sphere::new(
+                    Point3::new(i as f64 - 1.5, 0.0, -2.0),
+                    0.4,
+                    Arc::new(Lambertian::new(Color::red())),
+                ))
+            })
+            .collect();
+        let world = Node::new(&mut thread_rng(), &objects, 0.0, 1.0).unwrap();
+        let mut settings = RenderSettings::new(8, 8);
+        settings.samples_per_pixel = 2;
+
+        take();
+        render(
+            &world,
+            &Camera::default(),
+            &GradientSky::default(),
+            &settings,
+        );
+        let stats = take();
+
+        // Other tests may render at the same time, so these are lower bounds
+        assert!(stats.primary_rays >= 128);
+        assert!(stats.path_lengths.iter().sum::<u64>() >= 128);
+        assert!(stats.aabb_tests >= stats.primary_rays);
+        assert!(stats.aabb_tests >= stats.bvh_node_visits);
+        assert!(stats.primitive_tests > 0);
+        assert!(stats.phase("render").is_some());
+    }
+}
@@ -0,0 +1,135 @@
+use crate::camera::Camera;
+use crate::environment::environment::Environment;
+use crate::hit::Hit;
+use crate::image::Image;
+use crate::progressive::ProgressiveRender;
+use crate::render::RenderSettings;
+use crate::tone_mapping::ToneMapping;
+use std::io::{self, Write};
+
+const UPPER_HALF_BLOCK: char = '▀';
+
+/// Draws images into a terminal with 24-bit color, two pixels per character cell: the upper half
+/// block in the color of the top pixel over a background in the color of the bottom one. Every
+/// draw after the first overwrites the previous one in place.
+#[derive(Debug, Default)]
+pub struct TerminalPreview {
+    lines_drawn: usize,
+}
+
+impl TerminalPreview {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn draw<W: Write>(
+        &mut self,
+        out: &mut W,
+        image: &Image,
+        tone_mapping: &ToneMapping,
+    ) -> io::Result<()> {
+        if self.lines_drawn > 0 {
+            write!(out, "\x1b[{}F", self.lines_drawn)?;
+        }
+        let text = to_ansi(image, tone_mapping);
+        out.write_all(text.as_bytes())?;
+        out.flush()?;
+        self.lines_drawn = image.height().div_ceil(2);
+        Ok(())
+    }
+}
+
+/// `image` as lines of half blocks with truecolor escape codes. An odd last row is drawn over the
+/// terminal's own background.
+pub fn to_ansi(image: &Image, tone_mapping: &ToneMapping) -> String {
+    let mut text = String::new();
+
+    for y in (0..image.height()).step_by(2) {
+        for x in 0..image.width() {
+            let [r, g, b] = tone_mapping.to_rgb8(image.pixel(x, y));
+            text += &format!("\x1b[38;2;{};{};{}m", r, g, b);
+            if y + 1 < image.height() {
+                let [r, g, b] = tone_mapping.to_rgb8(image.pixel(x, y + 1));
+                text += &format!("\x1b[48;2;{};{};{}m", r, g, b);
+            }
+            text.push(UPPER_HALF_BLOCK);
+        }
+        text += "\x1b[0m\n";
+    }
+    text
+}
+
+/// `settings` scaled down to `columns` pixels across, keeping the aspect ratio. Character cells
+/// are about twice as tall as wide, so the half blocks come out roughly square. Never smaller
+/// than 2 by 2 pixels.
+pub fn preview_settings(settings: &RenderSettings, columns: usize) -> RenderSettings {
+    let scale = columns as f64 / settings.image_width as f64;
+    let mut preview = settings.clone();
+    preview.image_width = columns.max(2);
+    preview.image_height = ((settings.image_height as f64 * scale).round() as usize).max(2);
+    preview.crop = None;
+    preview
+}
+
+/// Renders `passes` progressive passes of `settings`, drawing the image to `out` after each.
+pub fn render_to_terminal<W: Write>(
+    world: &dyn Hit,
+    camera: &Camera,
+    environment: &dyn Environment,
+    settings: &RenderSettings,
+    passes: usize,
+    out: &mut W,
+) -> io::Result<Image> {
+    let mut render = ProgressiveRender::new(settings);
+    let mut preview = TerminalPreview::new();
+
+    for _ in 0..passes {
+        render.render_pass(world, camera, environment);
+        preview.draw(out, &render.image(), &settings.tone_mapping)?;
+    }
+    Ok(render.image())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector3::Color;
+
+    #[test]
+    fn pairs_of_rows_share_a_line() {
+        let mut image = Image::new(2, 3);
+        image.set_pixel(0, 0, Color::new(1.0, 0.0, 0.0));
+        image.set_pixel(0, 1, Color::new(0.0, 0.0, 1.0));
+
+        let text = to_ansi(&image, &ToneMapping::default());
+        let lines: Vec<_> = text.lines().collect();
+
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("\x1b[38;2;255;0;0m\x1b[48;2;0;0;255m▀"));
+        assert_eq!(lines[1].matches('▀').count(), 2);
+        assert!(!lines[1].contains("48;2"));
+    }
+
+    #[test]
+    fn narrow_terminals_get_at_least_two_by_two_pixels() {
+        let preview = preview_settings(&RenderSettings::new(300, 200), 1);
+        assert_eq!((preview.image_width, preview.image_height), (2, 2));
+    }
+
+    #[test]
+    fn redraws_move_back_over_the_previous_image() {
+        let mut preview = TerminalPreview::new();
+        let mut out = Vec::new();
+        let image = Image::new(3, 4);
+
+        preview
+            .draw(&mut out, &image, &ToneMapping::default())
+            .unwrap();
+        assert!(!out.starts_with(b"\x1b[2F"));
+        out.clear();
+        preview
+            .draw(&mut out, &image, &ToneMapping::default())
+            .unwrap();
+        assert!(out.starts_with(b"\x1b[2F"));
+    }
+}
@@ -0,0 +1,45 @@
+use crate::texture::texture::Texture;
+use crate::vector3::{Color, Point3};
+
+/// A solid checkerboard of cubes of side `size`, alternating between `even` and `odd`. Being
+/// defined in space rather than on the surface, it needs no texture coordinates.
+#[derive(Debug)]
+pub struct Checker {
+    even: Color,
+    odd: Color,
+    size: f64,
+}
+
+impl Checker {
+    pub fn new(even: Color, odd: Color, size: f64) -> Self {
+        Checker { even, odd, size }
+    }
+}
+
+impl Texture for Checker {
+    fn value(&self, _u: f64, _v: f64, p: &Point3) -> Color {
+        let cell =
+            (p.x() / self.size).floor() + (p.y() / self.size).floor() + (p.z() / self.size).floor();
+        if cell.rem_euclid(2.0) == 0.0 {
+            self.even.clone()
+        } else {
+            self.odd.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn neighboring_cells_alternate() {
+        let checker = Checker::new(Color::white(), Color::black(), 0.5);
+        let at = |x, y, z| checker.value(0.0, 0.0, &Point3::new(x, y, z));
+
+        assert_eq!(at(0.1, 0.1, 0.1), Color::white());
+        assert_eq!(at(0.6, 0.1, 0.1), Color::black());
+        assert_eq!(at(-0.1, 0.1, 0.1), Color::black());
+        assert_eq!(at(-0.1, -0.1, 0.1), Color::white());
+    }
+}
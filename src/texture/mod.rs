@@ -1,2 +1,4 @@
+pub mod checker;
 pub mod solid_color;
+#[allow(clippy::module_inception)]
 pub mod texture;
@@ -0,0 +1,191 @@
+use crate::to_pixel_value;
+use crate::vector3::{Color, Vector3};
+
+/// Compresses scene radiance into the displayable `[0, 1]` range.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ToneMapOperator {
+    /// Leaves values as they are and lets them clip at 1.
+    Clamp,
+    /// `L / (1 + L)` on luminance, which never quite reaches white.
+    Reinhard,
+    /// Reinhard with luminance `white_point` and above mapped to white.
+    ReinhardExtended { white_point: f64 },
+    /// John Hable's filmic curve from Uncharted 2.
+    Filmic,
+    /// Krzysztof Narkowicz's fit of the ACES reference rendering transform.
+    Aces,
+}
+
+/// Encodes display-referred linear values for an 8-bit file.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TransferFunction {
+    Linear,
+    /// A pure power curve; the book uses a gamma of 2.
+    Gamma(f64),
+    /// The piecewise sRGB curve from IEC 61966-2-1.
+    Srgb,
+}
+
+/// The steps applied to linear radiance when an image is written to a low dynamic range format:
+/// exposure, then the tone curve, then the transfer function.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ToneMapping {
+    /// In stops; each step doubles the brightness.
+    pub exposure: f64,
+    pub operator: ToneMapOperator,
+    pub transfer: TransferFunction,
+}
+
+impl ToneMapping {
+    pub fn new(operator: ToneMapOperator, transfer: TransferFunction) -> Self {
+        ToneMapping {
+            exposure: 0.0,
+            operator,
+            transfer,
+        }
+    }
+
+    pub fn with_exposure(mut self, exposure: f64) -> Self {
+        self.exposure = exposure;
+        self
+    }
+
+    /// Maps linear radiance to encoded values in `[0, 1]`.
+    pub fn map(&self, color: &Color) -> Color {
+        let exposed = Vector3::from(color.clone()) * 2f64.powf(self.exposure);
+        let mapped = self.operator.apply(&exposed);
+
+        Color::from(Vector3::new_from_iter(
+            mapped
+                .iter_elements()
+                .map(|c| self.transfer.encode(c.clamp(0.0, 1.0))),
+        ))
+    }
+
+    pub fn to_rgb8(&self, color: &Color) -> [u8; 3] {
+        let c = self.map(color);
+        [
+            to_pixel_value(c.r()),
+            to_pixel_value(c.g()),
+            to_pixel_value(c.b()),
+        ]
+    }
+}
+
+impl Default for ToneMapping {
+    /// Matches the output of the book: clip, then gamma 2.
+    fn default() -> Self {
+        Self::new(ToneMapOperator::Clamp, TransferFunction::Gamma(2.0))
+    }
+}
+
+impl ToneMapOperator {
+    fn apply(&self, c: &Vector3) -> Vector3 {
+        match *self {
+            Self::Clamp => c.clone(),
+            Self::Reinhard => scale_luminance(c, |l| l / (1.0 + l)),
+            Self::ReinhardExtended { white_point } => {
+                scale_luminance(c, |l| l * (1.0 + l / white_point.powi(2)) / (1.0 + l))
+            }
+            Self::Filmic => {
+                const EXPOSURE_BIAS: f64 = 2.0;
+                const WHITE: f64 = 11.2;
+                Vector3::new_from_iter(
+                    c.iter_elements()
+                        .map(|x| hable(x * EXPOSURE_BIAS) / hable(WHITE)),
+                )
+            }
+            Self::Aces => Vector3::new_from_iter(
+                c.iter_elements()
+                    .map(|x| (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14)),
+            ),
+        }
+    }
+}
+
+impl TransferFunction {
+    fn encode(&self, c: f64) -> f64 {
+        match *self {
+            Self::Linear => c,
+            Self::Gamma(gamma) => c.powf(1.0 / gamma),
+            Self::Srgb => {
+                if c <= 0.0031308 {
+                    12.92 * c
+                } else {
+                    1.055 * c.powf(1.0 / 2.4) - 0.055
+                }
+            }
+        }
+    }
+}
+
+pub fn luminance(c: &Vector3) -> f64 {
+    0.2126 * c.x() + 0.7152 * c.y() + 0.0722 * c.z()
+}
+
+fn scale_luminance<F: Fn(f64) -> f64>(c: &Vector3, curve: F) -> Vector3 {
+    let l = luminance(c);
+    if l <= 0.0 {
+        return Vector3::zero();
+    }
+    c * (curve(l) / l)
+}
+
+fn hable(x: f64) -> f64 {
+    const A: f64 = 0.15;
+    const B: f64 = 0.50;
+    const C: f64 = 0.10;
+    const D: f64 = 0.20;
+    const E: f64 = 0.02;
+    const F: f64 = 0.30;
+    ((x * (A * x + C * B) + D * E) / (x * (A * x + B) + D * F)) - E / F
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_matches_gamma_two() {
+        let tone_mapping = ToneMapping::default();
+        let c = Color::new(0.25, 0.04, 4.0);
+
+        assert_eq!(tone_mapping.to_rgb8(&c), [128, 51, 255]);
+    }
+
+    #[test]
+    fn operators_keep_highlights_below_white() {
+        let bright = Color::new_all(4.0);
+        for operator in [
+            ToneMapOperator::Reinhard,
+            ToneMapOperator::Filmic,
+            ToneMapOperator::Aces,
+        ] {
+            let mapped = ToneMapping::new(operator, TransferFunction::Linear).map(&bright);
+            assert!(mapped.r() < 1.0 && mapped.r() > 0.5, "{:?}", operator);
+        }
+
+        let extended = ToneMapping::new(
+            ToneMapOperator::ReinhardExtended { white_point: 4.0 },
+            TransferFunction::Linear,
+        );
+        assert!((extended.map(&bright).g() - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn exposure_is_in_stops() {
+        let tone_mapping =
+            ToneMapping::new(ToneMapOperator::Clamp, TransferFunction::Linear).with_exposure(1.0);
+
+        assert_eq!(tone_mapping.map(&Color::new_all(0.25)), Color::new_all(0.5));
+    }
+
+    #[test]
+    fn srgb_transfer() {
+        let srgb = TransferFunction::Srgb;
+
+        assert_eq!(srgb.encode(0.0), 0.0);
+        assert!((srgb.encode(1.0) - 1.0).abs() < 1e-12);
+        assert!((srgb.encode(0.18) - 0.4613).abs() < 1e-4);
+    }
+}
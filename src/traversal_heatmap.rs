@@ -0,0 +1,163 @@
+use crate::camera::Camera;
+use crate::false_color::{false_color, legend};
+use crate::hit::Hit;
+use crate::image::Image;
+use crate::render::{center_ray, RenderSettings};
+use crate::telemetry;
+use rayon::prelude::*;
+
+const LEGEND_GAP: usize = 4;
+const LEGEND_WIDTH: usize = 12;
+
+/// Which count a heatmap shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraversalCost {
+    AabbTests,
+    PrimitiveTests,
+    /// Both added up.
+    Total,
+}
+
+/// The work the primary ray through the center of each pixel needed to find its first hit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraversalCosts {
+    width: usize,
+    height: usize,
+    aabb_tests: Vec<u64>,
+    primitive_tests: Vec<u64>,
+}
+
+impl TraversalCosts {
+    pub fn width(&self) -> usize {
+        self.width
+    }
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize, cost: TraversalCost) -> u64 {
+        let i = y * self.width + x;
+        match cost {
+            TraversalCost::AabbTests => self.aabb_tests[i],
+            TraversalCost::PrimitiveTests => self.primitive_tests[i],
+            TraversalCost::Total => self.aabb_tests[i] + self.primitive_tests[i],
+        }
+    }
+
+    fn values(&self, cost: TraversalCost) -> impl Iterator<Item = u64> + '_ {
+        (0..self.height).flat_map(move |y| (0..self.width).map(move |x| self.get(x, y, cost)))
+    }
+
+    pub fn max(&self, cost: TraversalCost) -> u64 {
+        self.values(cost).max().unwrap_or(0)
+    }
+
+    pub fn mean(&self, cost: TraversalCost) -> f64 {
+        self.values(cost).sum::<u64>() as f64 / (self.width * self.height).max(1) as f64
+    }
+
+    /// Colors each pixel from blue for no tests to red for the most any pixel needed.
+    pub fn heatmap(&self, cost: TraversalCost) -> Image {
+        let max = self.max(cost).max(1) as f64;
+        Image::from_pixels(
+            self.width,
+            self.height,
+            self.values(cost)
+                .map(|c| false_color(c as f64 / max))
+                .collect(),
+        )
+    }
+
+    /// The heatmap with the [`legend`] to its right. Its ticks mark quarters of
+    /// [`max`](Self::max).
+    pub fn heatmap_with_legend(&self, cost: TraversalCost) -> Image {
+        let mut image = Image::new(self.width + LEGEND_GAP + LEGEND_WIDTH, self.height);
+        image.paste(&self.heatmap(cost), 0, 0);
+        image.paste(
+            &legend(LEGEND_WIDTH, self.height),
+            self.width + LEGEND_GAP,
+            0,
+        );
+        image
+    }
+}
+
+/// Traces one primary ray through the center of every pixel of the image `settings` describe and
+/// counts the tests it takes, showing where the BVH does poorly.
+pub fn traversal_costs(
+    world: &dyn Hit,
+    camera: &Camera,
+    settings: &RenderSettings,
+) -> TraversalCosts {
+    let width = settings.image_width;
+    let height = settings.image_height;
+
+    let (aabb_tests, primitive_tests) = (0..height)
+        .into_par_iter()
+        .flat_map_iter(|row| {
+            (0..width).map(move |i| {
+                let ray = center_ray(camera, (width, height), (i, row));
+                let (_, aabb_tests, primitive_tests) =
+                    telemetry::measure(|| world.hit(&ray, 0.001, f64::INFINITY));
+                (aabb_tests, primitive_tests)
+            })
+        })
+        .unzip();
+
+    TraversalCosts {
+        width,
+        height,
+        aabb_tests,
+        primitive_tests,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bvh::node::Node;
+    use crate::hit_objects::{HitObject, HitObjects};
+    use crate::material::lambertian::Lambertian;
+    use crate::sphere::Sphere;
+    use crate::vector3::{Color, Point3, Vector3};
+    use rand::thread_rng;
+    use std::sync::Arc;
+
+    #[test]
+    fn a_bvh_tests_fewer_primitives_than_a_list() {
+        let objects: Vec<_> = (0..16)
+            .map(|i| {
+                HitObject::Sphere(Sphere::new(
+                    Point3::new((i % 4) as f64 - 1.5, (i / 4) as f64 - 1.5, -4.0),
+                    0.3,
+                    Arc::new(Lambertian::new(Color::red())),
+                ))
+            })
+            .collect();
+        let list = HitObjects(objects.clone());
+        let bvh = Node::new(&mut thread_rng(), &objects, 0.0, 1.0).unwrap();
+        let camera = Camera::new(
+            Point3::zero(),
+            Point3::new_z(-1.0),
+            Vector3::new_y(1.0),
+            60.0,
+            1.0,
+            0.0,
+            1.0,
+            0.0,
+            1.0,
+        );
+        let settings = RenderSettings::new(16, 16);
+
+        let list_costs = traversal_costs(&list, &camera, &settings);
+        let bvh_costs = traversal_costs(&bvh, &camera, &settings);
+
+        assert_eq!(list_costs.max(TraversalCost::PrimitiveTests), 16);
+        assert_eq!(list_costs.max(TraversalCost::AabbTests), 0);
+        assert!(bvh_costs.mean(TraversalCost::PrimitiveTests) < 16.0);
+        assert!(bvh_costs.max(TraversalCost::AabbTests) > 0);
+
+        let image = bvh_costs.heatmap_with_legend(TraversalCost::Total);
+        assert_eq!(image.width(), 16 + LEGEND_GAP + LEGEND_WIDTH);
+    }
+}
@@ -1,9 +1,9 @@
 use crate::to_pixel_value;
-use rand::rngs::ThreadRng;
 use rand::{Rng, RngCore};
+use std::f64::consts::PI;
 use std::fmt::{Display, Formatter};
 use std::iter::Sum;
-use std::ops::{Add, AddAssign, Deref, DerefMut, Div, Mul, MulAssign, Neg, Range, Sub, SubAssign};
+use std::ops::{Add, AddAssign, Div, Mul, MulAssign, Neg, Range, Sub, SubAssign};
 
 const EPS: f64 = 1e-8;
 
@@ -88,7 +88,7 @@ impl Vector3 {
         self / self.length()
     }
 
-    pub fn random(rng: &mut ThreadRng) -> Self {
+    pub fn random<R: RngCore>(rng: &mut R) -> Self {
         Vector3::new(rng.gen(), rng.gen(), rng.gen())
     }
 
@@ -129,6 +129,36 @@ impl Vector3 {
         Self::random_in_unit_sphere(rng).unit_vector()
     }
 
+    /// Maps a point of the unit square uniformly onto the unit sphere.
+    pub fn unit_vector_from_square(u: f64, v: f64) -> Self {
+        let z = 1.0 - 2.0 * u;
+        let r = f64::sqrt((1.0 - z * z).max(0.0));
+        let phi = 2.0 * PI * v;
+        Self::new(r * phi.cos(), r * phi.sin(), z)
+    }
+
+    /// Maps a point of the unit cube uniformly into the unit ball.
+    pub fn in_unit_sphere_from_cube(u: f64, v: f64, w: f64) -> Self {
+        Self::unit_vector_from_square(u, v) * w.cbrt()
+    }
+
+    /// Maps a point of the unit square uniformly onto the unit disk in the xy plane, using
+    /// Shirley and Chiu's concentric mapping so that nearby points stay nearby.
+    pub fn in_unit_disk_from_square(u: f64, v: f64) -> Self {
+        let a = 2.0 * u - 1.0;
+        let b = 2.0 * v - 1.0;
+        if a == 0.0 && b == 0.0 {
+            return Self::zero();
+        }
+
+        let (r, theta) = if a.abs() > b.abs() {
+            (a, PI / 4.0 * (b / a))
+        } else {
+            (b, PI / 2.0 - PI / 4.0 * (a / b))
+        };
+        Self::new(r * theta.cos(), r * theta.sin(), 0.0)
+    }
+
     pub fn approx_zero(&self) -> bool {
         self.elements.iter().all(|x| x.abs() < EPS)
     }
@@ -348,7 +378,7 @@ impl Add<Vector3> for &Point3 {
     type Output = Point3;
 
     fn add(self, rhs: Vector3) -> Self::Output {
-        (&self).add(&rhs)
+        self.add(&rhs)
     }
 }
 
@@ -411,11 +441,11 @@ impl Color {
         self.0.z()
     }
 
-    pub fn random(rng: &mut ThreadRng) -> Self {
+    pub fn random<R: RngCore>(rng: &mut R) -> Self {
         Self(Vector3::random(rng))
     }
 
-    pub fn random_range(rng: &mut ThreadRng, range: Range<f64>) -> Self {
+    pub fn random_range<R: RngCore>(rng: &mut R, range: Range<f64>) -> Self {
         Self(Vector3::random_range(rng, range))
     }
 
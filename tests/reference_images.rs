@@ -0,0 +1,158 @@
+//! Renders small scenes with a fixed seed and compares them with the images in
+//! `tests/references`. A missing reference fails the test; set `UPDATE_REFERENCES=1` to write
+//! new references or rewrite them all after an intended change to the output. On failure the
+//! render and a false-color difference image are written to `target/reference-diffs`.
+
+use ray_tracing_in_one_week_rust::camera::Camera;
+use ray_tracing_in_one_week_rust::environment::gradient_sky::GradientSky;
+use ray_tracing_in_one_week_rust::hit_objects::{HitObject, HitObjects};
+use ray_tracing_in_one_week_rust::image::Image;
+use ray_tracing_in_one_week_rust::image_metrics::{compare, difference_image};
+use ray_tracing_in_one_week_rust::material::dielectric::Dielectric;
+use ray_tracing_in_one_week_rust::material::lambertian::Lambertian;
+use ray_tracing_in_one_week_rust::material::material::Material;
+use ray_tracing_in_one_week_rust::material::metal::Metal;
+use ray_tracing_in_one_week_rust::moving_sphere::MovingSphere;
+use ray_tracing_in_one_week_rust::render::{render, RenderSettings};
+use ray_tracing_in_one_week_rust::sphere::Sphere;
+use ray_tracing_in_one_week_rust::tone_mapping::ToneMapping;
+use ray_tracing_in_one_week_rust::vector3::{Color, Point3, Vector3};
+use std::env;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+use std::sync::Arc;
+
+const MAX_RMSE: f64 = 0.01;
+const MIN_PSNR: f64 = 40.0;
+const MIN_SSIM: f64 = 0.98;
+
+fn settings() -> RenderSettings {
+    let mut settings = RenderSettings::new(48, 32);
+    settings.samples_per_pixel = 16;
+    settings.max_depth = 8;
+    settings.seed = 1;
+    settings
+}
+
+fn camera() -> Camera {
+    Camera::new(
+        Point3::new(0.0, 1.0, 5.0),
+        Point3::new_y(0.6),
+        Vector3::new_y(1.0),
+        30.0,
+        1.5,
+        0.0,
+        5.0,
+        0.0,
+        1.0,
+    )
+}
+
+/// A single sphere of `material` on a grey ground, beside a diffuse sphere for it to reflect or
+/// refract.
+fn material_scene(material: Arc<dyn Material>) -> HitObjects {
+    let mut world = HitObjects::new();
+    world.add(HitObject::Sphere(Sphere::new(
+        Point3::new_y(-1000.0),
+        1000.0,
+        Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+    )));
+    world.add(HitObject::Sphere(Sphere::new(
+        Point3::new(-1.2, 0.4, -1.0),
+        0.4,
+        Arc::new(Lambertian::new(Color::new(0.8, 0.3, 0.1))),
+    )));
+    world.add(HitObject::Sphere(Sphere::new(
+        Point3::new_y(0.6),
+        0.6,
+        material,
+    )));
+    world
+}
+
+fn check(name: &str, world: &HitObjects, settings: &RenderSettings) {
+    let image = render(world, &camera(), &GradientSky::default(), settings);
+    let reference_path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/references")
+        .join(format!("{}.pfm", name));
+
+    if env::var_os("UPDATE_REFERENCES").is_some() {
+        let mut out = BufWriter::new(File::create(&reference_path).unwrap());
+        image.write_pfm(&mut out).unwrap();
+        return;
+    }
+
+    let reference_file = File::open(&reference_path).unwrap_or_else(|e| {
+        panic!(
+            "cannot open reference {}: {}; run with UPDATE_REFERENCES=1 to write it",
+            reference_path.display(),
+            e
+        )
+    });
+    let reference = Image::read_pfm(&mut BufReader::new(reference_file)).unwrap();
+    let comparison = compare(&reference, &image);
+    if comparison.rmse <= MAX_RMSE && comparison.psnr >= MIN_PSNR && comparison.ssim >= MIN_SSIM {
+        return;
+    }
+
+    let diff_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("target/reference-diffs");
+    fs::create_dir_all(&diff_dir).unwrap();
+    let actual_path = diff_dir.join(format!("{}.pfm", name));
+    image
+        .write_pfm(&mut BufWriter::new(File::create(&actual_path).unwrap()))
+        .unwrap();
+    let diff_path = diff_dir.join(format!("{}-diff.png", name));
+    difference_image(&reference, &image, 0.1)
+        .write_png(
+            &mut BufWriter::new(File::create(&diff_path).unwrap()),
+            &ToneMapping::default(),
+        )
+        .unwrap();
+
+    panic!(
+        "{} differs from its reference: {:?}; wrote {} and {}",
+        name,
+        comparison,
+        actual_path.display(),
+        diff_path.display()
+    );
+}
+
+#[test]
+fn lambertian() {
+    let world = material_scene(Arc::new(Lambertian::new(Color::new(0.1, 0.2, 0.5))));
+    check("lambertian", &world, &settings());
+}
+
+#[test]
+fn polished_metal() {
+    let world = material_scene(Arc::new(Metal::new(Color::new(0.8, 0.8, 0.8), 0.0)));
+    check("polished_metal", &world, &settings());
+}
+
+#[test]
+fn fuzzy_metal() {
+    let world = material_scene(Arc::new(Metal::new(Color::new(0.8, 0.6, 0.2), 0.3)));
+    check("fuzzy_metal", &world, &settings());
+}
+
+#[test]
+fn dielectric() {
+    let world = material_scene(Arc::new(Dielectric::new(1.5)));
+    check("dielectric", &world, &settings());
+}
+
+#[test]
+fn motion_blur() {
+    let mut world = material_scene(Arc::new(Lambertian::new(Color::new(0.1, 0.2, 0.5))));
+    world.add(HitObject::MovingSphere(MovingSphere::new(
+        Point3::new(1.0, 0.3, 0.5),
+        Point3::new(1.0, 0.6, 0.5),
+        0.3,
+        0.0,
+        1.0,
+        Arc::new(Lambertian::new(Color::new(0.2, 0.7, 0.2))),
+    )));
+    check("motion_blur", &world, &settings());
+}